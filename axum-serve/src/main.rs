@@ -1,22 +1,65 @@
-use std::{net::SocketAddr, str::FromStr, sync::Arc};
+use std::{
+    borrow::Borrow,
+    net::SocketAddr,
+    str::FromStr,
+    sync::{Arc, LazyLock},
+};
 
-use axum::{
-    extract::State,
-    response::IntoResponse,
-    routing::{get, post},
-    Json, Router,
+use axum::{response::IntoResponse, routing::post, Json, Router};
+use http::{HeaderName, HeaderValue, StatusCode};
+use minimax::{
+    available_threads,
+    eval::{explain_evaluation, BuiltinEvaluator, Evaluator},
+    tt::TranspositionTable,
+    BestMove, SearchConfig, SearchResult, StopHandle,
 };
-use dashmap::DashMap;
-use http::{HeaderName, HeaderValue};
-use minimax::minimax;
+use serde::Deserialize;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 use tower::ServiceBuilder;
 use tower_http::{cors::CorsLayer, services::ServeDir, set_header::SetResponseHeaderLayer};
-use ultimate_tic_tac_toe::{Board, IndividualBoard, LocalBoardState, MiniMaxResult, Player};
+use ultimate_tic_tac_toe::{
+    classic::ClassicBoard,
+    pie::{PieMove, PieResult, PieRule},
+    Board, Game, MiniMaxResult,
+};
+
+/// How the engine searches when the request does not say.
+static SEARCH_CONFIG: LazyLock<SearchConfig> = LazyLock::new(|| SearchConfig {
+    time_ms: Some(2000),
+    ..SearchConfig::default()
+});
+
+/// The most a request may ask of the server.
+const MAX_TIME_MS: u64 = 10_000;
+const MAX_NODES: u64 = 100_000_000;
+const MAX_MULTI_PV: usize = 5;
+const MAX_TABLE_MEGABYTES: usize = 256;
+
+/// Megabytes of table the `/search` requests running at once may hold between them. Requests
+/// past it wait for earlier ones to finish.
+const TOTAL_TABLE_MEGABYTES: usize = 1024;
+static TABLE_MEMORY: LazyLock<Arc<Semaphore>> =
+    LazyLock::new(|| Arc::new(Semaphore::new(TOTAL_TABLE_MEGABYTES)));
+
+/// Search threads the requests running at once may use between them, one per core. Requests
+/// past it wait for earlier ones to finish.
+static SEARCH_THREADS: LazyLock<Arc<Semaphore>> =
+    LazyLock::new(|| Arc::new(Semaphore::new(available_threads())));
+
+/// Kept between requests, so a game searched move after move starts from what it already knows.
+static TABLE: LazyLock<TranspositionTable<(usize, usize)>> =
+    LazyLock::new(|| TranspositionTable::with_megabytes(64));
+static PIE_TABLE: LazyLock<TranspositionTable<PieMove<(usize, usize)>>> =
+    LazyLock::new(|| TranspositionTable::with_megabytes(16));
 
 #[tokio::main]
 async fn main() {
     let app = Router::new()
         .route("/calc", post(calc))
+        .route("/classic/calc", post(classic_calc))
+        .route("/pie/calc", post(pie_calc))
+        .route("/search", post(search_with_config))
+        .route("/explain", post(explain))
         .nest_service("/", ServeDir::new("../client/dist"))
         .layer(
             ServiceBuilder::new()
@@ -37,12 +80,181 @@ async fn main() {
         .unwrap();
 }
 
+/// Stops a search when dropped along with the request it answers, which axum does when the
+/// client disconnects before the engine has replied.
+struct StopOnDrop(StopHandle);
+
+impl Drop for StopOnDrop {
+    fn drop(&mut self) {
+        self.0.stop();
+    }
+}
+
+/// Searches on a blocking thread so the runtime keeps serving other requests meanwhile, once
+/// [`SEARCH_THREADS`] has the threads the search asks for.
+///
+/// Positions without a move to suggest are rejected, since the request asks for one.
+async fn search<G: Game + 'static>(
+    game: G,
+    config: SearchConfig,
+    table: impl Borrow<TranspositionTable<G::Move>> + Send + 'static,
+) -> Result<SearchResult<G::Move>, (StatusCode, &'static str)>
+where
+    BuiltinEvaluator: Evaluator<G>,
+{
+    let threads = config.threads.clamp(1, available_threads());
+    let config = SearchConfig { threads, ..config };
+    let permit = SEARCH_THREADS
+        .clone()
+        .acquire_many_owned(threads as u32)
+        .await
+        .expect("the semaphore is never closed");
+
+    let stop = StopHandle::new();
+    let _stop_on_drop = StopOnDrop(stop.clone());
+
+    let result = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        config.search(&game, table.borrow(), Some(stop), |_| {})
+    })
+    .await
+    .expect("the search panicked");
+
+    match result {
+        BestMove::Best(result) => Ok(result),
+        BestMove::GameOver(_) => Err((StatusCode::UNPROCESSABLE_ENTITY, "The game is over")),
+        BestMove::NoLegalMoves => {
+            Err((StatusCode::UNPROCESSABLE_ENTITY, "There are no legal moves"))
+        }
+    }
+}
+
 async fn calc(Json(board): Json<Board>) -> impl IntoResponse {
-    let ((global, local), eval, _) = minimax(&board, 10, 2, f64::MIN, f64::MAX);
+    let SearchResult {
+        best_move: (global, local),
+        score,
+        pv,
+        ..
+    } = search(board, SEARCH_CONFIG.clone(), &*TABLE).await?;
 
-    Json(MiniMaxResult {
+    Ok::<_, (StatusCode, &str)>(Json(MiniMaxResult {
         global,
         local,
-        eval,
-    })
+        eval: score,
+        pv,
+    }))
+}
+
+async fn explain(Json(board): Json<Board>) -> impl IntoResponse {
+    Json(explain_evaluation(&board))
+}
+
+async fn pie_calc(Json(game): Json<PieRule<Board>>) -> impl IntoResponse {
+    let result = search(game, SEARCH_CONFIG.clone(), &*PIE_TABLE).await?;
+
+    Ok::<_, (StatusCode, &str)>(Json(PieResult {
+        mv: result.best_move,
+        eval: result.score,
+    }))
+}
+
+/// `config` with everything past the server's limits brought down to them.
+fn within_limits(config: SearchConfig) -> SearchConfig {
+    SearchConfig {
+        time_ms: Some(config.time_ms.unwrap_or(MAX_TIME_MS).min(MAX_TIME_MS)),
+        nodes: Some(config.nodes.unwrap_or(MAX_NODES).min(MAX_NODES)),
+        threads: config.threads.clamp(1, available_threads()),
+        table_megabytes: config.table_megabytes.min(MAX_TABLE_MEGABYTES),
+        multi_pv: config.multi_pv.clamp(1, MAX_MULTI_PV),
+        ..config
+    }
+}
+
+/// A table for one request, holding its share of [`TABLE_MEMORY`] for as long as it lives.
+struct RequestTable {
+    table: TranspositionTable<(usize, usize)>,
+    _memory: OwnedSemaphorePermit,
+}
+
+impl Borrow<TranspositionTable<(usize, usize)>> for RequestTable {
+    fn borrow(&self) -> &TranspositionTable<(usize, usize)> {
+        &self.table
+    }
+}
+
+#[derive(Deserialize)]
+struct SearchRequest {
+    board: Board,
+    #[serde(default)]
+    config: Option<SearchConfig>,
+}
+
+/// Searches as the request asks, within the server's limits, and answers with everything the
+/// search found. Each request gets a table of its own, since the size is up to the request,
+/// out of the memory [`TABLE_MEMORY`] has left.
+async fn search_with_config(Json(request): Json<SearchRequest>) -> impl IntoResponse {
+    let config = within_limits(request.config.unwrap_or_else(|| SEARCH_CONFIG.clone()));
+
+    // A deterministic search makes a second table of the same size to search in
+    let megabytes = config.table_megabytes * if config.deterministic { 2 } else { 1 };
+    let memory = TABLE_MEMORY
+        .clone()
+        .acquire_many_owned(megabytes as u32)
+        .await
+        .expect("the semaphore is never closed");
+    let table = RequestTable {
+        table: config.table(),
+        _memory: memory,
+    };
+
+    let result = search(request.board, config, table).await?;
+
+    Ok::<_, (StatusCode, &str)>(Json(result))
+}
+
+async fn classic_calc(Json(board): Json<ClassicBoard>) -> impl IntoResponse {
+    Json(board.analyze())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_bring_large_requests_down() {
+        let config = within_limits(SearchConfig {
+            time_ms: Some(60_000),
+            nodes: Some(u64::MAX),
+            threads: 1000,
+            table_megabytes: 100_000,
+            multi_pv: 81,
+            ..SearchConfig::default()
+        });
+
+        assert_eq!(config.time_ms, Some(MAX_TIME_MS));
+        assert_eq!(config.nodes, Some(MAX_NODES));
+        assert_eq!(config.threads, available_threads());
+        assert_eq!(config.table_megabytes, MAX_TABLE_MEGABYTES);
+        assert_eq!(config.multi_pv, MAX_MULTI_PV);
+    }
+
+    #[test]
+    fn limits_fill_in_missing_budgets_and_keep_small_requests() {
+        let config = within_limits(SearchConfig {
+            time_ms: None,
+            nodes: None,
+            threads: 0,
+            table_megabytes: 4,
+            multi_pv: 0,
+            max_depth: 5,
+            ..SearchConfig::default()
+        });
+
+        assert_eq!(config.time_ms, Some(MAX_TIME_MS));
+        assert_eq!(config.nodes, Some(MAX_NODES));
+        assert_eq!(config.threads, 1);
+        assert_eq!(config.table_megabytes, 4);
+        assert_eq!(config.multi_pv, 1);
+        assert_eq!(config.max_depth, 5);
+    }
 }
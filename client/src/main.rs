@@ -1,13 +1,102 @@
 use leptos::*;
 use reqwasm::http::Request;
-use ultimate_tic_tac_toe::{Board, IndividualBoard, MiniMaxResult, Player};
+use ultimate_tic_tac_toe::{
+    classic::{ClassicBoard, ClassicMoveResult},
+    pie::{PieMove, PieResult, PieRule, PieStage},
+    Board, EvaluationBreakdown, Game, IndividualBoard, MiniMaxResult, Player,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Ultimate,
+    Classic,
+}
 
 fn main() {
-    let (board, set_board) = create_signal(Board::default());
+    let (mode, set_mode) = create_signal(Mode::Ultimate);
+    let (pie_enabled, set_pie_enabled) = create_signal(false);
+    let (board, set_board) = create_signal(PieRule::without_swap(Board::default()));
+    let (classic, set_classic) = create_signal(ClassicBoard::default());
+    let (breakdown, set_breakdown) = create_signal(None::<EvaluationBreakdown>);
+    let (analysis, set_analysis) = create_signal(None::<MiniMaxResult>);
+
+    let reset_board = move || {
+        set_breakdown.set(None);
+        set_analysis.set(None);
+        set_board.set(if pie_enabled.get_untracked() {
+            PieRule::new(Board::default())
+        } else {
+            PieRule::without_swap(Board::default())
+        })
+    };
 
     let play = move |global: usize, local: usize| {
         let current_board = board.get();
 
+        if current_board.game.to_play == Player::O
+            || current_board.game.has_won().is_some()
+            || current_board.game.is_tie()
+        {
+            return;
+        }
+
+        if let Some(new_board) = current_board.play_move(PieMove::Play((global, local))) {
+            set_board.set(new_board.clone());
+            set_breakdown.set(None);
+
+            if new_board.game.has_won().is_some() || new_board.game.is_tie() {
+                return;
+            }
+
+            spawn_local(async move {
+                let (mv, new_analysis) = if new_board.stage == PieStage::SwapOffered {
+                    let response: PieResult<(usize, usize)> = Request::post("/pie/calc")
+                        .body(serde_json::to_string(&new_board).unwrap())
+                        .header("Content-Type", "application/json")
+                        .send()
+                        .await
+                        .unwrap()
+                        .json()
+                        .await
+                        .unwrap();
+
+                    (response.mv, None)
+                } else {
+                    let response: MiniMaxResult = Request::post("/calc")
+                        .body(serde_json::to_string(&new_board.game).unwrap())
+                        .header("Content-Type", "application/json")
+                        .send()
+                        .await
+                        .unwrap()
+                        .json()
+                        .await
+                        .unwrap();
+
+                    (
+                        PieMove::Play((response.global, response.local)),
+                        Some(response),
+                    )
+                };
+
+                // The game may have been reset while the engine was thinking, in which case the
+                // reply is for a board that is gone
+                if board.get_untracked() != new_board {
+                    return;
+                }
+
+                if let Some(response) = new_analysis {
+                    set_analysis.set(Some(response));
+                }
+                if let Some(new_board) = new_board.play_move(mv) {
+                    set_board.set(new_board);
+                }
+            })
+        }
+    };
+
+    let play_classic = move |local: usize| {
+        let current_board = classic.get();
+
         if current_board.to_play == Player::O
             || current_board.has_won().is_some()
             || current_board.is_tie()
@@ -15,15 +104,15 @@ fn main() {
             return;
         }
 
-        if let Some(new_board) = current_board.play(global, local) {
-            set_board.set(new_board.clone());
+        if let Some(new_board) = current_board.play(local) {
+            set_classic.set(new_board);
 
             if new_board.has_won().is_some() || new_board.is_tie() {
                 return;
             }
 
             spawn_local(async move {
-                let response: MiniMaxResult = Request::post("/calc")
+                let response: ClassicMoveResult = Request::post("/classic/calc")
                     .body(serde_json::to_string(&new_board).unwrap())
                     .header("Content-Type", "application/json")
                     .send()
@@ -33,18 +122,20 @@ fn main() {
                     .await
                     .unwrap();
 
-                set_board.set(
-                    board
-                        .get_untracked()
-                        .play(response.global, response.local)
-                        .expect("Invalid move!"),
-                );
+                // The game may have been reset while the engine was thinking
+                if classic.get_untracked() != new_board {
+                    return;
+                }
+
+                if let Some(new_board) = response.local.and_then(|local| new_board.play(local)) {
+                    set_classic.set(new_board);
+                }
             })
         }
     };
 
     let render_board = move || {
-        let board = board.get();
+        let board = board.get().game;
 
         (0..9)
             .map(|global| {
@@ -59,24 +150,144 @@ fn main() {
             .collect_view()
     };
 
+    let render_game = move || match mode.get() {
+        Mode::Ultimate => view! { <div class="grid grid-cols-3">{render_board}</div> }.into_view(),
+        Mode::Classic => view! {
+            <SingleBoard
+                board=classic.get().board
+                active=true
+                on_click=move |local| { play_classic(local) }
+            />
+        }
+        .into_view(),
+    };
+
+    let explain = move |_| {
+        let current_board = board.get_untracked().game;
+
+        spawn_local(async move {
+            let response: EvaluationBreakdown = Request::post("/explain")
+                .body(serde_json::to_string(&current_board).unwrap())
+                .header("Content-Type", "application/json")
+                .send()
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+
+            set_breakdown.set(Some(response));
+        })
+    };
+
+    let render_breakdown = move || {
+        breakdown.get().map(|breakdown| {
+            let locals = breakdown
+                .locals
+                .into_iter()
+                .map(|value| view! { <div class="text-center">{format!("{:+.3}", value)}</div> })
+                .collect_view();
+
+            view! {
+                <div class="grid grid-cols-3 w-[27rem] text-sm">{locals}</div>
+                <p class="text-sm">
+                    {format!("Global: {:+.3}, total: {:+.3}", breakdown.global, breakdown.total)}
+                </p>
+            }
+        })
+    };
+
+    let render_analysis = move || {
+        analysis.get().map(|analysis| {
+            let line = analysis
+                .pv
+                .iter()
+                .map(|(global, local)| format!("{global}:{local}"))
+                .collect::<Vec<_>>()
+                .join(" ");
+
+            view! {
+                <p class="text-sm">{format!("Engine: {}, expects {}", analysis.eval, line)}</p>
+            }
+        })
+    };
+
+    let status = move || {
+        let (winner, tie) = match mode.get() {
+            Mode::Ultimate => {
+                let board = board.get();
+
+                if board.stage == PieStage::Swapped && board.game.outcome().is_none() {
+                    return "The engine swapped and took your opening move!";
+                }
+
+                (board.game.has_won(), board.game.is_tie())
+            }
+            Mode::Classic => {
+                let board = classic.get();
+                (board.has_won(), board.is_tie())
+            }
+        };
+
+        if let Some(winner) = winner {
+            if winner == Player::X {
+                "You won!"
+            } else {
+                "You lost!"
+            }
+        } else if tie {
+            "Tie game!"
+        } else {
+            ""
+        }
+    };
+
     mount_to_body(move || {
         view! {
             <div class="absolute top-1/2 left-1/2 -translate-x-1/2 -translate-y-1/2 w-[27rem] h-[27rem]">
-                <div class="grid grid-cols-3">{render_board}</div>
-                <p>
-
-                    {move || {
-                        let board = board.get();
-                        if let Some(winner) = board.has_won() {
-                            if winner == Player::X { "You won!" } else { "You lost!" }
-                        } else if board.is_tie() {
-                            "Tie game!"
-                        } else {
-                            ""
+                <div class="flex gap-2 mb-2">
+                    <button
+                        class="px-2 border border-black"
+                        on:click=move |_| {
+                            reset_board();
+                            set_mode.set(Mode::Ultimate);
                         }
-                    }}
+                    >
 
-                </p>
+                        "Ultimate"
+                    </button>
+                    <button
+                        class="px-2 border border-black"
+                        on:click=move |_| {
+                            set_classic.set(ClassicBoard::default());
+                            set_mode.set(Mode::Classic);
+                        }
+                    >
+
+                        "Classic"
+                    </button>
+                    <label>
+                        <input
+                            type="checkbox"
+                            prop:checked=pie_enabled
+                            on:change=move |ev| {
+                                set_pie_enabled.set(event_target_checked(&ev));
+                                reset_board();
+                            }
+                        />
+
+                        " Swap rule"
+                    </label>
+                </div>
+                {render_game}
+                <p>{status}</p>
+                <Show when=move || mode.get() == Mode::Ultimate>
+                    {render_analysis}
+                    <button class="px-2 border border-black" on:click=explain>
+                        "Explain evaluation"
+                    </button>
+                    {render_breakdown}
+                </Show>
             </div>
         }
     })
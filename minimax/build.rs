@@ -1,10 +1,18 @@
 use std::{env, fs::File, io::Write, path::Path};
 
-use ultimate_tic_tac_toe::{evaluate_whole, evalute, Board, IndividualBoard, Player};
+use ultimate_tic_tac_toe::{
+    classic::ClassicBoard, evaluate_whole, evalute, Board, IndividualBoard, LocalBoardState, Player,
+};
 
 fn main() {
+    // The tables are computed at runtime instead, see `src/tables.rs`
+    if env::var_os("CARGO_FEATURE_RUNTIME_TABLES").is_some() {
+        return;
+    }
+
     let mut map = phf_codegen::Map::new();
     let mut whole_map = phf_codegen::Map::new();
+    let mut solution_map = phf_codegen::Map::new();
 
     for board in all_boards(0) {
         map.entry(
@@ -16,6 +24,17 @@ fn main() {
         );
     }
 
+    for board in all_boards(0) {
+        solution_map.entry(
+            board.key(),
+            &format!(
+                "[{}, {}]",
+                solution_literal(board, Player::X),
+                solution_literal(board, Player::O)
+            ),
+        );
+    }
+
     for board in all_global_boards(0) {
         whole_map.entry(
             board.key(),
@@ -29,19 +48,41 @@ fn main() {
     let path = Path::new(&env::var("OUT_DIR").unwrap()).join("codegen.rs");
     let mut file = File::create(&path).unwrap();
 
-    write!(
+    writeln!(
         file,
-        "static BOARD_EVALS: phf::Map<u32, f64> = {};\n",
+        "static BOARD_EVALS: phf::Map<u32, f64> = {};",
         map.build()
     )
     .unwrap();
 
-    write!(
+    writeln!(
         file,
-        "static WHOLE_BOARD_EVALS: phf::Map<u64, f64> = {};\n",
+        "static WHOLE_BOARD_EVALS: phf::Map<u64, f64> = {};",
         whole_map.build()
     )
     .unwrap();
+
+    writeln!(
+        file,
+        "static LOCAL_SOLUTIONS: phf::Map<u32, [ultimate_tic_tac_toe::classic::Solution; 2]> = {};",
+        solution_map.build()
+    )
+    .unwrap();
+}
+
+fn solution_literal(board: IndividualBoard, to_play: Player) -> String {
+    let solution = ClassicBoard { board, to_play }.solve();
+
+    let outcome = match solution.outcome {
+        LocalBoardState::Win(Player::X) => "Win(ultimate_tic_tac_toe::Player::X)",
+        LocalBoardState::Win(Player::O) => "Win(ultimate_tic_tac_toe::Player::O)",
+        LocalBoardState::Tie => "Tie",
+    };
+
+    format!(
+        "ultimate_tic_tac_toe::classic::Solution {{ outcome: ultimate_tic_tac_toe::LocalBoardState::{}, plies: {} }}",
+        outcome, solution.plies
+    )
 }
 
 fn all_boards(idx: usize) -> Vec<IndividualBoard> {
@@ -0,0 +1,75 @@
+//! Searches a position and prints the progress after every depth.
+//!
+//! Usage: `analyze [--config FILE] [--time SECS] [--depth N] [--nodes N] [--threads N]
+//! [--table MEGABYTES] [--multi-pv N] [--deterministic] [--seed N] [POSITION]`, where the
+//! config file holds a [`SearchConfig`] as JSON that the other flags change, and the position is
+//! a string like the one `Board::position_string` writes, the empty board if left out.
+
+use anyhow::{bail, Context};
+use minimax::{BestMove, SearchConfig};
+use ultimate_tic_tac_toe::Board;
+
+fn main() -> anyhow::Result<()> {
+    let mut config = SearchConfig::default();
+    let mut board = Board::default();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .with_context(|| format!("Missing value for {arg}"))
+        };
+
+        match arg.as_str() {
+            "--config" => {
+                let path = value()?;
+                let file = std::fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read {path}"))?;
+                config = serde_json::from_str(&file)?;
+            }
+            "--time" => config.time_ms = Some((value()?.parse::<f64>()? * 1000.0) as u64),
+            "--depth" => config.max_depth = value()?.parse()?,
+            "--nodes" => config.nodes = Some(value()?.parse()?),
+            "--threads" => config.threads = value()?.parse()?,
+            "--table" => config.table_megabytes = value()?.parse()?,
+            "--multi-pv" => config.multi_pv = value()?.parse()?,
+            "--deterministic" => config.deterministic = true,
+            "--seed" => config.seed = Some(value()?.parse()?),
+            _ if arg.starts_with("--") => bail!("Unknown argument {arg}"),
+            _ => board = arg.parse()?,
+        }
+    }
+
+    let result = config.search(&board, &config.table(), None, |result| {
+        let info = &result.info;
+
+        println!(
+            "depth {:>2}  score {:>12}  nodes {:>10}  cutoffs {:>9}  tt hits {:>9}  {:>6.2}s  {:>9.0} nps  pv {:?}",
+            info.depth,
+            result.score.to_string(),
+            info.nodes,
+            info.cutoffs,
+            info.table_hits,
+            info.elapsed.as_secs_f64(),
+            info.nodes_per_second(),
+            result.pv,
+        );
+
+        for line in &result.alternatives {
+            println!("{:10}score {:>12}  pv {:?}", "", line.score.to_string(), line.pv);
+        }
+    });
+
+    match result {
+        BestMove::Best(result) => println!(
+            "best move {:?}, {} nodes in {:.2}s",
+            result.best_move,
+            result.info.nodes,
+            result.info.elapsed.as_secs_f64()
+        ),
+        BestMove::GameOver(outcome) => println!("the game is over: {outcome:?}"),
+        BestMove::NoLegalMoves => println!("there are no legal moves"),
+    }
+
+    Ok(())
+}
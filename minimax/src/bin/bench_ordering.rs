@@ -0,0 +1,119 @@
+//! Counts the nodes a fixed depth search needs with each move ordering heuristic turned on in
+//! turn, over the same random positions.
+//!
+//! Usage: `bench_ordering [--positions N] [--plies N] [--depth N] [--seed N]`
+
+use anyhow::{bail, Context};
+use minimax::{
+    eval::TableEvaluator,
+    iterative_deepening,
+    openings::{generate_openings, OpeningConfig},
+    ordering::MoveOrdering,
+    rng::Rng,
+    tt::TranspositionTable,
+    Budget, SearchInfo, SearchOptions,
+};
+
+fn main() -> anyhow::Result<()> {
+    let mut positions = OpeningConfig {
+        count: 20,
+        plies: 8,
+        depth: 0,
+        max_eval: f64::INFINITY,
+        ..OpeningConfig::default()
+    };
+    let mut depth = 5;
+    let mut seed = 0;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .with_context(|| format!("Missing value for {arg}"))
+        };
+
+        match arg.as_str() {
+            "--positions" => positions.count = value()?.parse()?,
+            "--plies" => positions.plies = value()?.parse()?,
+            "--depth" => depth = value()?.parse()?,
+            "--seed" => seed = value()?.parse()?,
+            _ => bail!("Unknown argument {arg}"),
+        }
+    }
+
+    let boards = generate_openings(&positions, &mut Rng::new(seed));
+    let budget = Budget {
+        max_depth: depth,
+        ..Budget::default()
+    };
+
+    let orderings = [
+        ("none", MoveOrdering::NONE),
+        (
+            "priority",
+            MoveOrdering {
+                priority: true,
+                ..MoveOrdering::NONE
+            },
+        ),
+        (
+            "+ hash move",
+            MoveOrdering {
+                hash_move: true,
+                priority: true,
+                ..MoveOrdering::NONE
+            },
+        ),
+        (
+            "+ killers",
+            MoveOrdering {
+                history: false,
+                ..MoveOrdering::default()
+            },
+        ),
+        ("+ history", MoveOrdering::default()),
+    ];
+
+    println!(
+        "{} positions, {} random plies, depth {depth}",
+        boards.len(),
+        positions.plies
+    );
+
+    let mut baseline = None;
+    for (name, ordering) in orderings {
+        let (nodes, cutoffs) = boards
+            .iter()
+            .map(|(board, _)| {
+                // A fresh table for every search, so no ordering benefits from an earlier one
+                let table = TranspositionTable::with_megabytes(16);
+                let info = iterative_deepening(
+                    board,
+                    &TableEvaluator::default(),
+                    &budget,
+                    &table,
+                    &SearchOptions {
+                        threads: 1,
+                        ordering,
+                        ..SearchOptions::default()
+                    },
+                    |_| {},
+                )
+                .best()
+                .map_or(SearchInfo::default(), |result| result.info);
+
+                (info.nodes, info.cutoffs)
+            })
+            .fold((0, 0), |total, counts| {
+                (total.0 + counts.0, total.1 + counts.1)
+            });
+        let baseline = *baseline.get_or_insert(nodes);
+
+        println!(
+            "{name:>12}: {nodes:>12} nodes, {:>5.1}%, {cutoffs:>9} cutoffs",
+            100.0 * nodes as f64 / baseline as f64
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,108 @@
+//! Times a fixed depth search with different numbers of threads over the same random
+//! positions, reporting the speedup over a single thread.
+//!
+//! No Lazy SMP speedup has been measured yet. The only recorded run is from a single core
+//! machine, where `--threads 1,2,4` took 1.70s, 1.84s (0.92x) and 1.97s (0.86x): helpers
+//! without a core of their own only take time from the main search.
+//!
+//! Usage: `bench_threads [--threads N,N,..] [--positions N] [--plies N] [--depth N] [--seed N]`
+
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context};
+use minimax::{
+    available_threads,
+    eval::TableEvaluator,
+    iterative_deepening,
+    openings::{generate_openings, OpeningConfig},
+    rng::Rng,
+    tt::TranspositionTable,
+    Budget, SearchOptions,
+};
+
+fn main() -> anyhow::Result<()> {
+    let mut positions = OpeningConfig {
+        count: 10,
+        plies: 8,
+        depth: 0,
+        max_eval: f64::INFINITY,
+        ..OpeningConfig::default()
+    };
+    let mut depth = 8;
+    let mut seed = 0;
+    let mut thread_counts = (0..)
+        .map(|power| 1 << power)
+        .take_while(|&threads| threads <= available_threads())
+        .collect::<Vec<usize>>();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .with_context(|| format!("Missing value for {arg}"))
+        };
+
+        match arg.as_str() {
+            "--threads" => {
+                thread_counts = value()?
+                    .split(',')
+                    .map(str::parse)
+                    .collect::<Result<_, _>>()?
+            }
+            "--positions" => positions.count = value()?.parse()?,
+            "--plies" => positions.plies = value()?.parse()?,
+            "--depth" => depth = value()?.parse()?,
+            "--seed" => seed = value()?.parse()?,
+            _ => bail!("Unknown argument {arg}"),
+        }
+    }
+
+    let boards = generate_openings(&positions, &mut Rng::new(seed));
+    let budget = Budget {
+        max_depth: depth,
+        ..Budget::default()
+    };
+
+    println!(
+        "{} positions, {} random plies, depth {depth}, {} cores",
+        boards.len(),
+        positions.plies,
+        available_threads()
+    );
+
+    let mut baseline = None;
+    for threads in thread_counts {
+        let mut elapsed = Duration::ZERO;
+        let mut nodes = 0;
+
+        for (board, _) in &boards {
+            let table = TranspositionTable::with_megabytes(64);
+            let start = Instant::now();
+
+            let result = iterative_deepening(
+                board,
+                &TableEvaluator::default(),
+                &budget,
+                &table,
+                &SearchOptions {
+                    threads,
+                    ..SearchOptions::default()
+                },
+                |_| {},
+            );
+
+            elapsed += start.elapsed();
+            nodes += result.best().map_or(0, |result| result.info.nodes);
+        }
+
+        let baseline = *baseline.get_or_insert(elapsed);
+
+        println!(
+            "{threads:>3} threads: {:>8.2}s, {:>5.2}x speedup, {nodes:>12} nodes",
+            elapsed.as_secs_f64(),
+            baseline.as_secs_f64() / elapsed.as_secs_f64()
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,109 @@
+//! Plays Monte Carlo tree search against alpha-beta from the same random openings, each engine
+//! taking both sides of every opening, and prints the score of MCTS.
+//!
+//! Usage: `mcts_match [--games N] [--playouts N] [--threads N] [--depth N] [--plies N] [--seed N]`
+
+use anyhow::{bail, Context};
+use minimax::{
+    mcts::{Mcts, MctsConfig},
+    openings::{generate_openings, OpeningConfig},
+    rng::Rng,
+    BestMove, SearchConfig,
+};
+use ultimate_tic_tac_toe::{Board, Game, Outcome, Player};
+
+fn main() -> anyhow::Result<()> {
+    let mut positions = OpeningConfig {
+        count: 10,
+        plies: 4,
+        depth: 0,
+        max_eval: f64::INFINITY,
+        ..OpeningConfig::default()
+    };
+    let mut config = MctsConfig {
+        playouts: Some(20_000),
+        ..MctsConfig::default()
+    };
+    let mut alpha_beta = SearchConfig {
+        max_depth: 4,
+        time_ms: None,
+        threads: 1,
+        ..SearchConfig::default()
+    };
+    let mut seed = 0;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .with_context(|| format!("Missing value for {arg}"))
+        };
+
+        match arg.as_str() {
+            "--games" => positions.count = value()?.parse()?,
+            "--playouts" => config.playouts = Some(value()?.parse()?),
+            "--threads" => config.threads = value()?.parse()?,
+            "--depth" => alpha_beta.max_depth = value()?.parse()?,
+            "--plies" => positions.plies = value()?.parse()?,
+            "--seed" => seed = value()?.parse()?,
+            _ => bail!("Unknown argument {arg}"),
+        }
+    }
+
+    let openings = generate_openings(&positions, &mut Rng::new(seed));
+    let mut score = 0.0;
+    let mut games = 0;
+
+    for (opening, _) in &openings {
+        for mcts_plays in [Player::X, Player::O] {
+            let outcome = play(opening.clone(), mcts_plays, &config, &alpha_beta);
+            let result = match outcome {
+                Outcome::Win(player) if player == mcts_plays => 1.0,
+                Outcome::Win(_) => 0.0,
+                Outcome::Tie => 0.5,
+            };
+
+            score += result;
+            games += 1;
+            println!(
+                "MCTS as {mcts_plays:?} from {}: {result}",
+                opening.position_string()
+            );
+        }
+    }
+
+    println!(
+        "MCTS scored {score} of {games} against depth {} alpha-beta, {:.1}%",
+        alpha_beta.max_depth,
+        100.0 * score / games as f64
+    );
+
+    Ok(())
+}
+
+/// Plays one game, with the same MCTS engine throughout so it keeps its tree between moves.
+fn play(
+    mut board: Board,
+    mcts_plays: Player,
+    config: &MctsConfig,
+    alpha_beta: &SearchConfig,
+) -> Outcome {
+    let mut mcts = Mcts::new(config.clone());
+    let table = alpha_beta.table();
+
+    loop {
+        let best = if board.side_to_move() == mcts_plays {
+            mcts.search(&board).map(|result| result.best_move)
+        } else {
+            alpha_beta
+                .search(&board, &table, None, |_| {})
+                .map(|result| result.best_move)
+        };
+
+        board = match best {
+            BestMove::Best(mv) => board.play_move(mv).expect("the engines play legal moves"),
+            BestMove::GameOver(outcome) => return outcome,
+            BestMove::NoLegalMoves => return Outcome::Tie,
+        };
+    }
+}
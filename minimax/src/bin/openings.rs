@@ -0,0 +1,54 @@
+//! Generates balanced openings for engine matches.
+//!
+//! Usage: `openings [--plies N] [--count N] [--depth N] [--max-eval F] [--seed N] [--out PATH]`
+
+use std::{fs::File, io, time::SystemTime};
+
+use anyhow::{bail, Context};
+use minimax::{
+    openings::{generate_openings, write_openings, OpeningConfig},
+    rng::Rng,
+};
+
+fn main() -> anyhow::Result<()> {
+    let mut config = OpeningConfig::default();
+    let mut seed = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)?
+        .as_nanos() as u64;
+    let mut out = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .with_context(|| format!("Missing value for {arg}"))
+        };
+
+        match arg.as_str() {
+            "--plies" => config.plies = value()?.parse()?,
+            "--count" => config.count = value()?.parse()?,
+            "--depth" => config.depth = value()?.parse()?,
+            "--max-eval" => config.max_eval = value()?.parse()?,
+            "--seed" => seed = value()?.parse()?,
+            "--out" => out = Some(value()?),
+            _ => bail!("Unknown argument {arg}"),
+        }
+    }
+
+    let openings = generate_openings(&config, &mut Rng::new(seed));
+
+    eprintln!(
+        "Generated {} of {} openings with seed {seed}",
+        openings.len(),
+        config.count
+    );
+
+    let boards = openings.iter().map(|(board, _)| board);
+
+    match out {
+        Some(path) => write_openings(boards, File::create(path)?)?,
+        None => write_openings(boards, io::stdout().lock())?,
+    }
+
+    Ok(())
+}
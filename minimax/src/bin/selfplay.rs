@@ -0,0 +1,78 @@
+//! Plays the engine against itself and writes every position with the game result, for tuning.
+//!
+//! Usage: `selfplay [--games N] [--depth N] [--epsilon F] [--openings PATH] [--plies N]
+//! [--seed N] [--out PATH]`
+
+use std::{
+    fs::File,
+    io::{self, BufReader},
+    time::SystemTime,
+};
+
+use anyhow::{bail, Context};
+use minimax::{
+    eval::TableEvaluator,
+    openings::{random_opening, read_openings},
+    rng::Rng,
+    selfplay::{play_game, write_samples, SelfPlayConfig},
+};
+
+fn main() -> anyhow::Result<()> {
+    let mut config = SelfPlayConfig::default();
+    let mut games = 100;
+    let mut plies = 4;
+    let mut openings = None;
+    let mut seed = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)?
+        .as_nanos() as u64;
+    let mut out = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .with_context(|| format!("Missing value for {arg}"))
+        };
+
+        match arg.as_str() {
+            "--games" => games = value()?.parse()?,
+            "--depth" => config.depth = value()?.parse()?,
+            "--epsilon" => config.epsilon = value()?.parse()?,
+            "--openings" => openings = Some(read_openings(BufReader::new(File::open(value()?)?))?),
+            "--plies" => plies = value()?.parse()?,
+            "--seed" => seed = value()?.parse()?,
+            "--out" => out = Some(value()?),
+            _ => bail!("Unknown argument {arg}"),
+        }
+    }
+
+    let mut rng = Rng::new(seed);
+    let mut samples = Vec::new();
+
+    for game in 0..games {
+        let start = match &openings {
+            Some(openings) if !openings.is_empty() => openings[game % openings.len()].clone(),
+            // Too many plies can end the game first, which leaves nothing to play
+            _ => random_opening(plies, &mut rng).unwrap_or_default(),
+        };
+
+        samples.append(&mut play_game(
+            start,
+            &TableEvaluator::default(),
+            &config,
+            &mut rng,
+        ));
+    }
+
+    eprintln!(
+        "Played {games} games for {} positions with seed {seed}",
+        samples.len()
+    );
+
+    match out {
+        Some(path) => write_samples(&samples, File::create(path)?)?,
+        None => write_samples(&samples, io::stdout().lock())?,
+    }
+
+    Ok(())
+}
@@ -0,0 +1,60 @@
+//! Trains the neural network evaluation on self-play results.
+//!
+//! Usage: `train_nn --data PATH [--init PATH] [--hidden N] [--epochs N] [--rate F] [--seed N]
+//! [--out PATH]`
+
+use std::{fs::File, io::BufReader};
+
+use anyhow::{bail, Context};
+use minimax::{
+    nn::{train, Network, TrainConfig},
+    rng::Rng,
+    selfplay::read_samples,
+};
+
+fn main() -> anyhow::Result<()> {
+    let mut config = TrainConfig::default();
+    let mut data = None;
+    let mut init = None;
+    let mut hidden = 32;
+    let mut seed = 0;
+    let mut out = "network.json".to_string();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .with_context(|| format!("Missing value for {arg}"))
+        };
+
+        match arg.as_str() {
+            "--data" => data = Some(value()?),
+            "--init" => init = Some(value()?),
+            "--hidden" => hidden = value()?.parse()?,
+            "--epochs" => config.epochs = value()?.parse()?,
+            "--rate" => config.learning_rate = value()?.parse()?,
+            "--seed" => seed = value()?.parse()?,
+            "--out" => out = value()?,
+            _ => bail!("Unknown argument {arg}"),
+        }
+    }
+
+    let data = data.context("Missing --data")?;
+    let samples = read_samples(BufReader::new(File::open(data)?))?;
+    let mut rng = Rng::new(seed);
+
+    let mut network = match init {
+        Some(path) => Network::load(path)?,
+        None => Network::new(hidden, &mut rng),
+    };
+
+    eprintln!("Training on {} samples", samples.len());
+
+    train(&mut network, &samples, &config, &mut rng, |epoch, error| {
+        eprintln!("Epoch {epoch}: error {error}")
+    });
+
+    network.save(&out)?;
+
+    Ok(())
+}
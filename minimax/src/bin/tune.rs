@@ -0,0 +1,57 @@
+//! Fits the weights of the linear evaluation to self-play results.
+//!
+//! Usage: `tune --data PATH [--init PATH] [--iterations N] [--rate F] [--scale F] [--out PATH]`
+
+use std::{fs::File, io::BufReader};
+
+use anyhow::{bail, Context};
+use minimax::{
+    linear::{mean_error, tune, TunerConfig, Weights},
+    selfplay::read_samples,
+};
+
+fn main() -> anyhow::Result<()> {
+    let mut config = TunerConfig::default();
+    let mut data = None;
+    let mut weights = Weights::default();
+    let mut out = "weights.json".to_string();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .with_context(|| format!("Missing value for {arg}"))
+        };
+
+        match arg.as_str() {
+            "--data" => data = Some(value()?),
+            "--init" => weights = Weights::load(value()?)?,
+            "--iterations" => config.iterations = value()?.parse()?,
+            "--rate" => config.learning_rate = value()?.parse()?,
+            "--scale" => config.scale = value()?.parse()?,
+            "--out" => out = value()?,
+            _ => bail!("Unknown argument {arg}"),
+        }
+    }
+
+    let data = data.context("Missing --data")?;
+    let samples = read_samples(BufReader::new(File::open(data)?))?;
+
+    eprintln!(
+        "Loaded {} samples, error {}",
+        samples.len(),
+        mean_error(&samples, weights, config.scale)
+    );
+
+    let weights = tune(&samples, weights, &config);
+
+    eprintln!(
+        "Tuned error {}",
+        mean_error(&samples, weights, config.scale)
+    );
+
+    weights.save(&out)?;
+    println!("{}", serde_json::to_string_pretty(&weights)?);
+
+    Ok(())
+}
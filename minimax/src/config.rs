@@ -0,0 +1,141 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use ultimate_tic_tac_toe::Game;
+
+use crate::{
+    available_threads,
+    eval::{BuiltinEvaluator, Evaluator},
+    iterative_deepening,
+    ordering::MoveOrdering,
+    tt::TranspositionTable,
+    BestMove, Budget, SearchOptions, SearchResult, StopHandle,
+};
+
+/// Everything about a search in one place, for callers that take it from a user or over the
+/// network instead of writing out a [`Budget`] and [`SearchOptions`]. Missing fields take
+/// their default when deserialized.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchConfig {
+    /// The deepest iteration, see [`Budget::max_depth`].
+    pub max_depth: u64,
+    /// Milliseconds to search for, unlimited if left out.
+    pub time_ms: Option<u64>,
+    pub nodes: Option<u64>,
+    pub threads: usize,
+    /// The size of the transposition table [`SearchConfig::table`] makes.
+    pub table_megabytes: usize,
+    pub evaluator: BuiltinEvaluator,
+    /// How many of the best moves to report, see [`SearchOptions::multi_pv`].
+    pub multi_pv: usize,
+    /// See [`SearchOptions::deterministic`].
+    pub deterministic: bool,
+    /// Picks among equally good moves with this seed, see [`SearchOptions::seed`].
+    pub seed: Option<u64>,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        SearchConfig {
+            max_depth: 80,
+            time_ms: Some(5000),
+            nodes: None,
+            threads: available_threads(),
+            table_megabytes: 64,
+            evaluator: BuiltinEvaluator::default(),
+            multi_pv: 1,
+            deterministic: false,
+            seed: None,
+        }
+    }
+}
+
+impl SearchConfig {
+    pub fn budget(&self, stop: Option<StopHandle>) -> Budget {
+        Budget {
+            max_depth: self.max_depth,
+            time: self.time_ms.map(Duration::from_millis),
+            nodes: self.nodes,
+            stop,
+        }
+    }
+
+    pub fn options(&self) -> SearchOptions {
+        SearchOptions {
+            threads: self.threads,
+            ordering: MoveOrdering::default(),
+            deterministic: self.deterministic,
+            seed: self.seed,
+            multi_pv: self.multi_pv,
+        }
+    }
+
+    /// An empty transposition table of the configured size.
+    pub fn table<M: Copy>(&self) -> TranspositionTable<M> {
+        TranspositionTable::with_megabytes(self.table_megabytes)
+    }
+
+    /// Runs [`iterative_deepening`] as configured. The table is passed in rather than made
+    /// here so it can be kept between searches.
+    pub fn search<G: Game>(
+        &self,
+        game: &G,
+        table: &TranspositionTable<G::Move>,
+        stop: Option<StopHandle>,
+        on_depth: impl FnMut(&SearchResult<G::Move>),
+    ) -> BestMove<SearchResult<G::Move>>
+    where
+        BuiltinEvaluator: Evaluator<G>,
+    {
+        iterative_deepening(
+            game,
+            &self.evaluator,
+            &self.budget(stop),
+            table,
+            &self.options(),
+            on_depth,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_fields_take_their_defaults() {
+        let config: SearchConfig = serde_json::from_str(r#"{"max_depth": 6}"#).unwrap();
+        let default = SearchConfig::default();
+
+        assert_eq!(config.max_depth, 6);
+        assert_eq!(config.time_ms, default.time_ms);
+        assert_eq!(config.nodes, default.nodes);
+        assert_eq!(config.threads, default.threads);
+        assert_eq!(config.table_megabytes, default.table_megabytes);
+        assert!(matches!(config.evaluator, BuiltinEvaluator::Table(_)));
+        assert_eq!(config.multi_pv, default.multi_pv);
+        assert!(!config.deterministic);
+        assert_eq!(config.seed, None);
+    }
+
+    #[test]
+    fn configs_round_trip() {
+        let config = SearchConfig {
+            max_depth: 7,
+            time_ms: None,
+            nodes: Some(1000),
+            threads: 3,
+            table_megabytes: 8,
+            evaluator: BuiltinEvaluator::Outcome,
+            multi_pv: 2,
+            deterministic: true,
+            seed: Some(9),
+        };
+
+        let json = serde_json::to_string(&config).unwrap();
+        let read: SearchConfig = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(serde_json::to_string(&read).unwrap(), json);
+    }
+}
@@ -0,0 +1,224 @@
+use serde::{Deserialize, Serialize};
+use ultimate_tic_tac_toe::{
+    classic::Solution,
+    general::GeneralBoard,
+    tables::{global_value, local_value},
+    Board, EvaluationBreakdown, Game, IndividualBoard, LocalBoardState, Outcome, Player,
+};
+
+use crate::tables;
+
+/// A static evaluation the search falls back on at its depth limit, positive when X is ahead.
+pub trait Evaluator<G: Game>: Send + Sync {
+    fn evaluate(&self, game: &G) -> f64;
+}
+
+/// Implements [`Evaluator`] for the [`PieRule`] version of every game an evaluator handles, by
+/// evaluating the game inside. The marks on the board are the same whoever ends up owning them.
+macro_rules! evaluate_inside_pie_rule {
+    ($($evaluator:ty),+ $(,)?) => {$(
+        impl<G: ultimate_tic_tac_toe::pie::Swappable>
+            $crate::eval::Evaluator<ultimate_tic_tac_toe::pie::PieRule<G>> for $evaluator
+        where
+            $evaluator: $crate::eval::Evaluator<G>,
+        {
+            fn evaluate(&self, game: &ultimate_tic_tac_toe::pie::PieRule<G>) -> f64 {
+                self.evaluate(&game.game)
+            }
+        }
+    )+};
+}
+
+pub(crate) use evaluate_inside_pie_rule;
+
+/// How [`TableEvaluator`] values a local board.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LocalValues {
+    /// The average outcome if both players play uniformly at random.
+    #[default]
+    RandomPlayout,
+    /// The game theoretic value, with faster wins worth more.
+    PerfectPlay,
+}
+
+/// The precomputed values of every local board and of the global board, see [`crate::tables`].
+/// This is the default evaluator for [`Board`].
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TableEvaluator {
+    pub local: LocalValues,
+}
+
+fn solution_value(solution: Solution) -> f64 {
+    match solution.outcome {
+        Outcome::Win(Player::X) => 1.0 - solution.plies as f64 / 20.0,
+        Outcome::Win(Player::O) => -1.0 + solution.plies as f64 / 20.0,
+        Outcome::Tie => 0.0,
+    }
+}
+
+impl TableEvaluator {
+    fn local_value(&self, local: IndividualBoard) -> f64 {
+        match self.local {
+            LocalValues::RandomPlayout => tables::local_eval(local),
+            LocalValues::PerfectPlay => {
+                let [x_to_play, o_to_play] = tables::local_solutions(local);
+
+                (solution_value(x_to_play) + solution_value(o_to_play)) / 2.0
+            }
+        }
+    }
+
+    fn global_value(&self, whole_board: &Board) -> f64 {
+        tables::whole_board_eval(whole_board)
+    }
+
+    /// Splits the evaluation into what each local board and the global board add to it.
+    pub fn explain(&self, whole_board: &Board) -> EvaluationBreakdown {
+        EvaluationBreakdown {
+            locals: std::array::from_fn(|board_idx| {
+                self.local_value(whole_board.get_local(board_idx)) / 9.0
+            }),
+            global: self.global_value(whole_board),
+            total: self.evaluate(whole_board),
+        }
+    }
+}
+
+impl Evaluator<Board> for TableEvaluator {
+    fn evaluate(&self, whole_board: &Board) -> f64 {
+        let sum = (0..9)
+            .map(|board_idx| self.local_value(whole_board.get_local(board_idx)))
+            .sum::<f64>()
+            / 9.0;
+
+        sum + self.global_value(whole_board)
+    }
+}
+
+/// The breakdown of the default evaluation, see [`TableEvaluator::explain`].
+pub fn explain_evaluation(whole_board: &Board) -> EvaluationBreakdown {
+    TableEvaluator::default().explain(whole_board)
+}
+
+/// Like [`TableEvaluator`], but values the global board and the board the next move is sent to
+/// with the side to move, and rewards the side to move when it may play anywhere.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContextEvaluator {
+    /// Added for the side to move when it is not forced into a board.
+    pub free_move_bonus: f64,
+    /// Extra weight on the board the side to move is forced into, relative to the others.
+    pub destination_weight: f64,
+}
+
+impl Default for ContextEvaluator {
+    fn default() -> Self {
+        ContextEvaluator {
+            free_move_bonus: 0.05,
+            destination_weight: 1.0,
+        }
+    }
+}
+
+impl Evaluator<Board> for ContextEvaluator {
+    fn evaluate(&self, game: &Board) -> f64 {
+        let to_play = game.to_play;
+        let sign = match to_play {
+            Player::X => 1.0,
+            Player::O => -1.0,
+        };
+
+        let locals = (0..9)
+            .map(|board_idx| {
+                let local = game.get_local(board_idx);
+
+                if game.global_idx == Some(board_idx) {
+                    (1.0 + self.destination_weight) * local_value(local, to_play)
+                } else {
+                    (local_value(local, Player::X) + local_value(local, Player::O)) / 2.0
+                }
+            })
+            .sum::<f64>()
+            / 9.0;
+
+        let free_move = if game.global_idx.is_none() {
+            sign * self.free_move_bonus
+        } else {
+            0.0
+        };
+
+        locals + global_value(game, to_play) + free_move
+    }
+}
+
+/// The share of local boards won by X minus the share won by O.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WonBoardsEvaluator;
+
+impl Evaluator<Board> for WonBoardsEvaluator {
+    fn evaluate(&self, game: &Board) -> f64 {
+        (game.global_x.count_ones() as f64 - game.global_o.count_ones() as f64) / 9.0
+    }
+}
+
+impl Evaluator<GeneralBoard> for WonBoardsEvaluator {
+    fn evaluate(&self, game: &GeneralBoard) -> f64 {
+        game.globals
+            .iter()
+            .map(|state| match state {
+                Some(LocalBoardState::Win(Player::X)) => 1.0,
+                Some(LocalBoardState::Win(Player::O)) => -1.0,
+                _ => 0.0,
+            })
+            .sum::<f64>()
+            / game.boards() as f64
+    }
+}
+
+/// Knows nothing beyond the rules, for games small enough to search to the end.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OutcomeEvaluator;
+
+impl<G: Game> Evaluator<G> for OutcomeEvaluator {
+    fn evaluate(&self, _game: &G) -> f64 {
+        0.0
+    }
+}
+
+/// One of the evaluators above, picked at runtime, for example by a [`crate::SearchConfig`]
+/// that arrived over the network. The learned evaluators are left out since they need their
+/// weights loaded from a file.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BuiltinEvaluator {
+    Table(TableEvaluator),
+    Context(ContextEvaluator),
+    WonBoards,
+    Outcome,
+}
+
+impl Default for BuiltinEvaluator {
+    fn default() -> Self {
+        BuiltinEvaluator::Table(TableEvaluator::default())
+    }
+}
+
+impl Evaluator<Board> for BuiltinEvaluator {
+    fn evaluate(&self, game: &Board) -> f64 {
+        match self {
+            BuiltinEvaluator::Table(evaluator) => evaluator.evaluate(game),
+            BuiltinEvaluator::Context(evaluator) => evaluator.evaluate(game),
+            BuiltinEvaluator::WonBoards => WonBoardsEvaluator.evaluate(game),
+            BuiltinEvaluator::Outcome => OutcomeEvaluator.evaluate(game),
+        }
+    }
+}
+
+evaluate_inside_pie_rule!(
+    TableEvaluator,
+    ContextEvaluator,
+    WonBoardsEvaluator,
+    BuiltinEvaluator,
+);
@@ -1,224 +1,1043 @@
-use std::{cmp::Ordering, sync::atomic};
+use std::{
+    hash::Hash,
+    num::NonZeroUsize,
+    sync::{
+        atomic::{self, AtomicBool, AtomicU64},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
-use dashmap::DashMap;
-use rayon::iter::{IntoParallelIterator, ParallelIterator};
-use ultimate_tic_tac_toe::{Board, Player};
+use serde::Serialize;
+use ultimate_tic_tac_toe::{Game, Outcome, Player, Score};
 
-include!(concat!(env!("OUT_DIR"), "/codegen.rs"));
+use crate::{
+    eval::Evaluator,
+    ordering::{MoveOrdering, OrderingState},
+    tt::{Bound, Entry, TranspositionTable},
+};
 
-pub struct AtomicF64 {
-    storage: atomic::AtomicU64,
+mod config;
+pub mod eval;
+pub mod linear;
+pub mod mcts;
+pub mod nn;
+pub mod openings;
+pub mod ordering;
+pub mod rng;
+pub mod selfplay;
+pub mod tables;
+pub mod tt;
+
+pub use config::SearchConfig;
+
+fn terminal_score(outcome: Outcome, ply: u32) -> Score {
+    match outcome {
+        Outcome::Tie => Score::DRAW,
+        Outcome::Win(player) => Score::win_in(player, ply),
+    }
 }
 
-impl AtomicF64 {
-    pub fn new(value: f64) -> Self {
-        let as_u64 = value.to_bits();
-        Self {
-            storage: atomic::AtomicU64::new(as_u64),
-        }
+/// Picks the best child for `to_play`. A child that scored inside its window is exact and wins
+/// ties against one that failed low or high, whose score is only a bound.
+fn best<M>(to_play: Player, results: impl Iterator<Item = (M, Score, bool)>) -> Option<(M, Score)> {
+    let (mv, score, _) = if to_play == Player::X {
+        results.reduce(|best, next| {
+            if (next.1, next.2) > (best.1, best.2) {
+                next
+            } else {
+                best
+            }
+        })?
+    } else {
+        results.reduce(|best, next| {
+            if (next.1, !next.2) < (best.1, !best.2) {
+                next
+            } else {
+                best
+            }
+        })?
+    };
+
+    Some((mv, score))
+}
+
+/// Stops a running search from another thread. Clones share the same flag.
+#[derive(Clone, Debug, Default)]
+pub struct StopHandle(Arc<AtomicBool>);
+
+impl StopHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn stop(&self) {
+        self.0.store(true, atomic::Ordering::Relaxed);
     }
-    pub fn store(&self, value: f64, ordering: atomic::Ordering) {
-        let as_u64 = value.to_bits();
-        self.storage.store(as_u64, ordering)
+
+    pub fn is_stopped(&self) -> bool {
+        self.0.load(atomic::Ordering::Relaxed)
     }
-    pub fn load(&self, ordering: atomic::Ordering) -> f64 {
-        let as_u64 = self.storage.load(ordering);
-        f64::from_bits(as_u64)
+}
+
+/// How [`iterative_deepening`] goes about its search.
+#[derive(Clone, Debug)]
+pub struct SearchOptions {
+    /// Threads searching at once, the others helping the first through the table. More threads
+    /// than cores only slow the search down, see the `bench_threads` binary.
+    pub threads: usize,
+    pub ordering: MoveOrdering,
+    /// Searches on one thread in an empty table of the same size as the one passed in, which is
+    /// left untouched, so the result only depends on the position and the options. The table
+    /// is allocated anew on every search, which costs as much as making one of that size.
+    ///
+    /// A time limit still makes how deep it gets depend on the machine, so reproducible
+    /// searches should be limited by depth or nodes instead.
+    pub deterministic: bool,
+    /// Picks at random among moves that score the same instead of the first one found,
+    /// the same way every time for the same seed.
+    pub seed: Option<u64>,
+    /// How many of the best moves to find a score and line for, each searched again without
+    /// the ones before it.
+    pub multi_pv: usize,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        SearchOptions {
+            threads: available_threads(),
+            ordering: MoveOrdering::default(),
+            deterministic: false,
+            seed: None,
+            multi_pv: 1,
+        }
     }
 }
 
-pub fn evaluate(whole_board: &Board) -> f64 {
-    let sum = (0..9)
-        .map(|board_idx| {
-            let key = whole_board.get_local(board_idx).key();
+/// Limits on how much work [`iterative_deepening`] may do.
+#[derive(Clone, Debug)]
+pub struct Budget {
+    /// The deepest iteration. Depth 0 scores the children of the root with the evaluator.
+    pub max_depth: u64,
+    pub time: Option<Duration>,
+    pub nodes: Option<u64>,
+    /// Ends the search early once stopped, as if the budget had run out.
+    pub stop: Option<StopHandle>,
+}
+
+impl Default for Budget {
+    fn default() -> Self {
+        Budget {
+            max_depth: 80,
+            time: None,
+            nodes: None,
+            stop: None,
+        }
+    }
+}
+
+/// The state shared by every node of one search.
+struct Search<'a, E, M> {
+    evaluator: &'a E,
+    table: Option<&'a TranspositionTable<M>>,
+    ordering: OrderingState<M>,
+    deadline: Option<Instant>,
+    node_limit: Option<u64>,
+    stop: Option<StopHandle>,
+    nodes: AtomicU64,
+    cutoffs: AtomicU64,
+    table_hits: AtomicU64,
+    stopped: AtomicBool,
+}
+
+impl<'a, E, M: Copy + Eq + Hash + Send + Sync> Search<'a, E, M> {
+    fn new(
+        evaluator: &'a E,
+        table: Option<&'a TranspositionTable<M>>,
+        ordering: OrderingState<M>,
+        deadline: Option<Instant>,
+        node_limit: Option<u64>,
+        stop: Option<StopHandle>,
+    ) -> Self {
+        Search {
+            evaluator,
+            table,
+            ordering,
+            deadline,
+            node_limit,
+            stop,
+            nodes: AtomicU64::new(0),
+            cutoffs: AtomicU64::new(0),
+            table_hits: AtomicU64::new(0),
+            stopped: AtomicBool::new(false),
+        }
+    }
+
+    /// Adds the work this search has done so far to `info`.
+    fn count_into(&self, info: &mut SearchInfo) {
+        info.nodes += self.nodes.load(atomic::Ordering::Relaxed);
+        info.cutoffs += self.cutoffs.load(atomic::Ordering::Relaxed);
+        info.table_hits += self.table_hits.load(atomic::Ordering::Relaxed);
+    }
+
+    fn is_stopped(&self) -> bool {
+        self.stopped.load(atomic::Ordering::Relaxed)
+    }
+
+    /// Counts a node, returning false once the budget has run out.
+    fn visit(&self) -> bool {
+        if self.is_stopped() {
+            return false;
+        }
+
+        let nodes = self.nodes.fetch_add(1, atomic::Ordering::Relaxed) + 1;
+
+        // Reading the clock on every node would cost more than the nodes themselves
+        let out_of_time = nodes.is_multiple_of(1024)
+            && self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline);
+        let out_of_nodes = self.node_limit.is_some_and(|limit| nodes > limit);
+        let stopped = self.stop.as_ref().is_some_and(StopHandle::is_stopped);
+
+        if out_of_time || out_of_nodes || stopped {
+            self.stopped.store(true, atomic::Ordering::Relaxed);
+            return false;
+        }
+
+        true
+    }
+
+    /// Scores a position without searching, `ply` plies below the root.
+    fn leaf<G: Game>(&self, game: &G, ply: u32) -> Option<Score>
+    where
+        E: Evaluator<G>,
+    {
+        if !self.visit() {
+            return None;
+        }
 
-            BOARD_EVALS.get(&key).unwrap()
+        Some(match game.outcome() {
+            Some(outcome) => terminal_score(outcome, ply),
+            None => Score::from_eval(self.evaluator.evaluate(game)),
         })
-        .sum::<f64>()
-        / 9.0;
+    }
+
+    /// The stored result for `key`, if it was searched deep enough and settles the position
+    /// within the window.
+    fn settled(
+        entry: Option<Entry<M>>,
+        depth: u64,
+        ply: u32,
+        alpha: Score,
+        beta: Score,
+    ) -> Option<(Option<M>, Score)> {
+        let entry = entry.filter(|entry| entry.depth >= depth)?;
+
+        let score = tt::from_table(entry.score, ply);
+        let settled = match entry.bound {
+            Bound::Exact => true,
+            Bound::Lower => score >= beta,
+            Bound::Upper => score <= alpha,
+        };
 
-    let key = whole_board.key();
+        settled.then_some((entry.best_move, score))
+    }
+
+    /// Stores the result of searching a position with the window `alpha..beta`.
+    fn store(
+        &self,
+        key: u64,
+        depth: u64,
+        ply: u32,
+        alpha: Score,
+        beta: Score,
+        (mv, score): (Option<M>, Score),
+    ) {
+        let Some(table) = self.table else {
+            return;
+        };
 
-    let sum2 = WHOLE_BOARD_EVALS.get(&key).unwrap();
+        let bound = if score <= alpha {
+            Bound::Upper
+        } else if score >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
 
-    sum + sum2
+        table.store(Entry {
+            key,
+            depth,
+            score: tt::to_table(score, ply),
+            bound,
+            best_move: mv,
+        });
+    }
+
+    /// The best move and its score, with no move if the game is over or there are no legal
+    /// moves, or `None` if the budget ran out first.
+    fn alpha_beta<G: Game<Move = M>>(
+        &self,
+        game: &G,
+        depth: u64,
+        ply: u32,
+        alpha: Score,
+        beta: Score,
+    ) -> Option<(Option<G::Move>, Score)>
+    where
+        E: Evaluator<G>,
+    {
+        self.alpha_beta_excluding(game, depth, ply, alpha, beta, &[])
+    }
+
+    /// Like [`Search::alpha_beta`], but never plays one of the `excluded` moves from `game`.
+    /// The result is not the position's own, so it neither comes from nor goes to the table.
+    fn alpha_beta_excluding<G: Game<Move = M>>(
+        &self,
+        game: &G,
+        depth: u64,
+        ply: u32,
+        mut alpha: Score,
+        mut beta: Score,
+        excluded: &[M],
+    ) -> Option<(Option<G::Move>, Score)>
+    where
+        E: Evaluator<G>,
+    {
+        if !self.visit() {
+            return None;
+        }
+
+        if let Some(outcome) = game.outcome() {
+            return Some((None, terminal_score(outcome, ply)));
+        }
+
+        let key = game.hash_key();
+        let entry = self.table.and_then(|table| table.probe(key));
+        if entry.is_some() {
+            self.table_hits.fetch_add(1, atomic::Ordering::Relaxed);
+        }
+        if let Some(result) = Self::settled(entry, depth, ply, alpha, beta) {
+            if excluded.is_empty() {
+                return Some(result);
+            }
+        }
+
+        let (alpha_before, beta_before) = (alpha, beta);
+        let to_play = game.side_to_move();
+
+        let results = self
+            .ordering
+            .order(game, entry.and_then(|entry| entry.best_move), ply)
+            .into_iter()
+            .filter(|mv| !excluded.contains(mv))
+            .filter_map(|mv| Some((mv, game.play_move(mv)?)))
+            .filter_map(|(mv, child)| {
+                if beta <= alpha {
+                    return None;
+                }
+
+                Some(if depth == 0 {
+                    (mv, self.leaf(&child, ply + 1)?, true)
+                } else {
+                    let (_, value) = self.alpha_beta(&child, depth - 1, ply + 1, alpha, beta)?;
+                    let exact = alpha < value && value < beta;
+
+                    if to_play == Player::X {
+                        alpha = alpha.max(value);
+                    } else {
+                        beta = beta.min(value);
+                    }
+
+                    if beta <= alpha {
+                        self.cutoffs.fetch_add(1, atomic::Ordering::Relaxed);
+                        self.ordering.record_cutoff(mv, depth, ply);
+                    }
+
+                    (mv, value, exact)
+                })
+            });
+
+        let best = best(to_play, results);
+
+        if self.is_stopped() {
+            return None;
+        }
+
+        // Stuck without a legal move in an unfinished game, all that is left is to evaluate it
+        let best = match best {
+            Some((mv, score)) => (Some(mv), score),
+            None => (None, Score::from_eval(self.evaluator.evaluate(game))),
+        };
+        if excluded.is_empty() {
+            self.store(key, depth, ply, alpha_before, beta_before, best);
+        }
+
+        Some(best)
+    }
+
+    /// Runs `main` on this thread while `threads - 1` helpers search `game` alongside it, one
+    /// depth after another up to `max_depth`, stopping them once `main` returns.
+    ///
+    /// This is Lazy SMP: the helpers share nothing with `main` but the transposition table and
+    /// the move ordering state, so they never wait on each other, and what they store lets
+    /// `main` skip or better order parts of its own search. Every other helper starts one ply
+    /// deeper so they spread over more of the tree.
+    fn with_helpers<G: Game<Move = M>, T>(
+        &self,
+        game: &G,
+        threads: usize,
+        max_depth: u64,
+        main: impl FnOnce() -> T,
+    ) -> T
+    where
+        E: Evaluator<G>,
+    {
+        if threads <= 1 || self.table.is_none() {
+            return main();
+        }
+
+        std::thread::scope(|scope| {
+            for helper in 1..threads {
+                scope.spawn(move || {
+                    for depth in 1 + helper as u64 % 2..=max_depth {
+                        if self
+                            .alpha_beta(game, depth, 0, Score::MIN, Score::MAX)
+                            .is_none()
+                        {
+                            break;
+                        }
+                    }
+                });
+            }
+
+            let result = main();
+            self.stopped.store(true, atomic::Ordering::Relaxed);
+            result
+        })
+    }
 }
 
-pub fn minimax_single(
-    whole_board: &Board,
+/// What a search makes of a position.
+#[derive(Clone, Debug)]
+pub enum BestMove<T> {
+    /// The game is over, so there is nothing to search.
+    GameOver(Outcome),
+    /// The game is not over, but the side to move has no legal move.
+    NoLegalMoves,
+    Best(T),
+}
+
+impl<T> BestMove<T> {
+    pub fn best(self) -> Option<T> {
+        match self {
+            BestMove::Best(best) => Some(best),
+            BestMove::GameOver(_) | BestMove::NoLegalMoves => None,
+        }
+    }
+
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> BestMove<U> {
+        match self {
+            BestMove::GameOver(outcome) => BestMove::GameOver(outcome),
+            BestMove::NoLegalMoves => BestMove::NoLegalMoves,
+            BestMove::Best(best) => BestMove::Best(f(best)),
+        }
+    }
+}
+
+fn root_result<G: Game>(
+    game: &G,
+    (mv, score): (Option<G::Move>, Score),
+) -> BestMove<(G::Move, Score)> {
+    match (game.outcome(), mv) {
+        (Some(outcome), _) => BestMove::GameOver(outcome),
+        (None, Some(mv)) => BestMove::Best((mv, score)),
+        (None, None) => BestMove::NoLegalMoves,
+    }
+}
+
+/// Searches `depth` plies past the children of `game` on this thread with the full window,
+/// with every score counting plies from the root so faster wins score higher. Nothing is kept
+/// between calls, so the result only depends on the arguments.
+pub(crate) fn fixed_depth<G: Game, E: Evaluator<G>>(
+    game: &G,
+    evaluator: &E,
     depth: u64,
-    mut alpha: f64,
-    mut beta: f64,
-) -> ((usize, usize), f64, u64) {
-    if whole_board.is_tie() {
-        return ((0, 0), 0.0, depth);
-    } else if let Some(player) = whole_board.has_won() {
-        if depth == 4 {
-            return (
-                (0, 0),
-                match player {
-                    Player::X => 10.0,
-                    Player::O => -10.0,
-                },
-                depth,
-            );
-        }
-        return (
-            (0, 0),
-            match player {
-                Player::X => 10.0,
-                Player::O => -10.0,
-            },
-            depth,
-        );
+) -> BestMove<(G::Move, Score)> {
+    let result = Search::new(
+        evaluator,
+        None,
+        OrderingState::new(MoveOrdering::default(), None),
+        None,
+        None,
+        None,
+    )
+    .alpha_beta(game, depth, 0, Score::MIN, Score::MAX)
+    .expect("a search without a budget always finishes");
+
+    root_result(game, result)
+}
+
+/// The number of threads the machine can run at once, for searches that should use all of it.
+pub fn available_threads() -> usize {
+    std::thread::available_parallelism().map_or(1, NonZeroUsize::get)
+}
+
+/// How much work a search did.
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+pub struct SearchInfo {
+    /// The depth of the last iteration that finished.
+    pub depth: u64,
+    /// Positions visited across every iteration, including one cut short.
+    pub nodes: u64,
+    /// Positions where a move was good enough to skip the rest.
+    pub cutoffs: u64,
+    /// Positions found in the transposition table, whether or not the entry could be used.
+    pub table_hits: u64,
+    pub elapsed: Duration,
+}
+
+impl SearchInfo {
+    pub fn nodes_per_second(&self) -> f64 {
+        if self.elapsed.is_zero() {
+            return 0.0;
+        }
+
+        self.nodes as f64 / self.elapsed.as_secs_f64()
     }
+}
+
+/// What [`iterative_deepening`] found.
+#[derive(Clone, Debug, Serialize)]
+pub struct SearchResult<M> {
+    pub best_move: M,
+    pub score: Score,
+    /// The line of play the search expects, starting with `best_move`.
+    pub pv: Vec<M>,
+    /// The next best moves after `best_move`, best first, as many as
+    /// [`SearchOptions::multi_pv`] asks for beyond the first.
+    pub alternatives: Vec<Line<M>>,
+    pub info: SearchInfo,
+}
+
+/// A move other than the best one, with what it scores and the line of play expected after it.
+#[derive(Clone, Debug, Serialize)]
+pub struct Line<M> {
+    pub score: Score,
+    /// Starts with the move.
+    pub pv: Vec<M>,
+}
 
-    let range = if let Some(idx) = whole_board.global_idx {
-        idx * 9..idx * 9 + 9
+/// Searches one ply deeper at a time until the budget runs out, returning the result of the
+/// last iteration that finished. `on_depth` is called with the result so far after every
+/// iteration that finishes.
+///
+/// The first iteration always finishes, so there is a move even with an empty budget as long
+/// as the game has one. Results are kept in `table` for the next iteration and for later
+/// searches that share it. With more than one thread, the others search alongside through
+/// `table`.
+pub fn iterative_deepening<G: Game, E: Evaluator<G>>(
+    game: &G,
+    evaluator: &E,
+    budget: &Budget,
+    table: &TranspositionTable<G::Move>,
+    options: &SearchOptions,
+    mut on_depth: impl FnMut(&SearchResult<G::Move>),
+) -> BestMove<SearchResult<G::Move>> {
+    let start = Instant::now();
+
+    if let Some(outcome) = game.outcome() {
+        return BestMove::GameOver(outcome);
+    }
+
+    // A deterministic search must not see what earlier searches left in the caller's table,
+    // nor clear it for whoever else is sharing it
+    let private;
+    let (table, threads) = if options.deterministic {
+        private = TranspositionTable::with_entries(table.capacity());
+        (&private, 1)
     } else {
-        0..81
+        (table, options.threads)
+    };
+    table.new_search();
+
+    let ordering = || OrderingState::new(options.ordering, options.seed);
+
+    let first = Search::new(evaluator, Some(table), ordering(), None, None, None);
+    let (Some(best_move), score) = first
+        .alpha_beta(game, 0, 0, Score::MIN, Score::MAX)
+        .expect("a search without a budget always finishes")
+    else {
+        return BestMove::NoLegalMoves;
     };
 
-    let results = range
-        .filter(|idx| ((whole_board.locals_x | whole_board.locals_o) >> idx) & 1 == 0)
-        .map(|idx| (idx / 9, idx % 9))
-        .filter_map(|(global, local)| Some(((global, local), whole_board.play(global, local)?)))
-        .filter_map(|(pos, board)| {
-            if beta <= alpha {
-                return None;
+    let pv = principal_variation(game, best_move, table, 1);
+    let alternatives = next_best_lines(&first, game, 0, best_move, options.multi_pv, table)
+        .expect("a search without a budget always finishes");
+
+    let mut first_info = SearchInfo::default();
+    first.count_into(&mut first_info);
+
+    let mut result = SearchResult {
+        best_move,
+        score,
+        pv,
+        alternatives,
+        info: SearchInfo {
+            elapsed: start.elapsed(),
+            ..first_info
+        },
+    };
+    on_depth(&result);
+
+    let search = Search::new(
+        evaluator,
+        Some(table),
+        ordering(),
+        budget.time.map(|time| start + time),
+        budget.nodes,
+        budget.stop.clone(),
+    );
+    let info = |depth| {
+        let mut info = SearchInfo {
+            depth,
+            elapsed: start.elapsed(),
+            ..first_info
+        };
+        search.count_into(&mut info);
+        info
+    };
+
+    search.with_helpers(game, threads, budget.max_depth, || {
+        for depth in 1..=budget.max_depth {
+            // Deeper searches cannot change a forced result inside the horizon. One past it may
+            // have come from a deeper entry in the table, with a faster win still to be found.
+            let solved = result
+                .score
+                .win()
+                .is_some_and(|(_, plies)| plies as u64 <= depth);
+            if solved {
+                break;
             }
 
-            Some(if depth == 0 {
-                (pos, evaluate(whole_board), depth)
-            } else {
-                let (_, value, eval_depth) = minimax_single(&board, depth - 1, alpha, beta);
+            match search.alpha_beta(game, depth, 0, Score::MIN, Score::MAX) {
+                Some((Some(mv), value)) => {
+                    let pv = principal_variation(game, mv, table, depth + 1);
+                    let Some(alternatives) =
+                        next_best_lines(&search, game, depth, mv, options.multi_pv, table)
+                    else {
+                        break;
+                    };
 
-                if whole_board.to_play == Player::X {
-                    alpha = alpha.max(value);
-                } else {
-                    beta = beta.min(value);
+                    result = SearchResult {
+                        best_move: mv,
+                        score: value,
+                        pv,
+                        alternatives,
+                        info: info(depth),
+                    };
+                    on_depth(&result);
                 }
+                Some((None, _)) | None => break,
+            }
+        }
+    });
+
+    result.info = info(result.info.depth);
+    BestMove::Best(result)
+}
 
-                (pos, value, eval_depth)
-            })
+/// The moves that come closest to `best_move` at `depth`, until there are `count` moves in all
+/// or no more moves, or `None` if the budget ran out first.
+fn next_best_lines<G: Game, E: Evaluator<G>>(
+    search: &Search<E, G::Move>,
+    game: &G,
+    depth: u64,
+    best_move: G::Move,
+    count: usize,
+    table: &TranspositionTable<G::Move>,
+) -> Option<Vec<Line<G::Move>>> {
+    let mut excluded = vec![best_move];
+    let mut lines = Vec::new();
+
+    while excluded.len() < count {
+        let (Some(mv), score) =
+            search.alpha_beta_excluding(game, depth, 0, Score::MIN, Score::MAX, &excluded)?
+        else {
+            break;
+        };
+
+        excluded.push(mv);
+        lines.push(Line {
+            score,
+            pv: principal_variation(game, mv, table, depth + 1),
         });
+    }
 
-    let res = if whole_board.to_play == Player::X {
-        results
-            .max_by(|(_, eval_a, depth_a), (_, eval_b, depth_b)| {
-                match eval_a.partial_cmp(eval_b).unwrap() {
-                    Ordering::Equal => depth_a.cmp(depth_b),
-                    n => n,
-                }
-            })
-            .unwrap()
-    } else {
-        results
-            .min_by(|(_, eval_a, depth_a), (_, eval_b, depth_b)| {
-                match eval_a.partial_cmp(eval_b).unwrap() {
-                    Ordering::Equal => depth_b.cmp(depth_a),
-                    n => n,
-                }
-            })
-            .unwrap()
-    };
+    Some(lines)
+}
 
-    res
+/// Follows the best moves stored in `table` from `game`, starting with `first`, for at most
+/// `length` plies. Only exact entries are followed, the best move of a bound is just the move
+/// that caused the cutoff.
+fn principal_variation<G: Game>(
+    game: &G,
+    first: G::Move,
+    table: &TranspositionTable<G::Move>,
+    length: u64,
+) -> Vec<G::Move> {
+    let mut pv = Vec::new();
+    let mut position = game.clone();
+    let mut next = Some(first);
+
+    while let Some(mv) = next.filter(|_| (pv.len() as u64) < length && position.outcome().is_none())
+    {
+        let Some(child) = position.play_move(mv) else {
+            break;
+        };
+
+        pv.push(mv);
+        position = child;
+        next = table
+            .probe(position.hash_key())
+            .filter(|entry| entry.bound == Bound::Exact)
+            .and_then(|entry| entry.best_move);
+    }
+
+    pv
 }
 
-pub fn minimax(
-    whole_board: &Board,
-    depth: u64,
-    threaded_depth: u64,
-    alpha: f64,
-    beta: f64,
-) -> ((usize, usize), f64, u64) {
-    if whole_board.is_tie() {
-        return ((0, 0), 0.0, depth);
-    } else if let Some(player) = whole_board.has_won() {
-        if depth == 4 {
-            return (
-                (0, 0),
-                match player {
-                    Player::X => 10.0,
-                    Player::O => -10.0,
-                },
-                depth,
-            );
-        }
-        return (
-            (0, 0),
-            match player {
-                Player::X => 10.0,
-                Player::O => -10.0,
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use ultimate_tic_tac_toe::{classic::ClassicBoard, Board, IndividualBoard};
+
+    use super::*;
+    use crate::{
+        eval::{OutcomeEvaluator, TableEvaluator},
+        openings::random_opening,
+        rng::Rng,
+    };
+
+    #[test]
+    fn classic_empty_board_is_a_draw() {
+        let result = fixed_depth(&ClassicBoard::default(), &OutcomeEvaluator, 8);
+
+        let BestMove::Best((_, score)) = result else {
+            panic!("the empty board has moves");
+        };
+        assert_eq!(score, Score::DRAW);
+    }
+
+    #[test]
+    fn classic_immediate_win_is_found() {
+        // X holds the first two squares of the top row and O two of the middle row
+        let board = ClassicBoard {
+            board: IndividualBoard(0b000_000_011, 0b000_011_000),
+            to_play: Player::X,
+        };
+
+        let result = fixed_depth(&board, &OutcomeEvaluator, 8);
+
+        let BestMove::Best((mv, score)) = result else {
+            panic!("the board has moves");
+        };
+        assert_eq!(mv, 2);
+        assert_eq!(score, Score::win_in(Player::X, 1));
+    }
+
+    #[test]
+    fn classic_scores_agree_with_the_solver() {
+        let mut seen = HashSet::new();
+        let mut stack = vec![ClassicBoard::default()];
+        while let Some(board) = stack.pop() {
+            if seen.insert(board) {
+                stack.extend(
+                    board
+                        .moves()
+                        .into_iter()
+                        .filter_map(|mv| board.play_move(mv)),
+                );
+            }
+        }
+
+        let mut positions = seen.into_iter().collect::<Vec<_>>();
+        positions.sort_by_key(|board| (board.board.0, board.board.1));
+
+        for board in positions.into_iter().step_by(23) {
+            let BestMove::Best((_, score)) = fixed_depth(&board, &OutcomeEvaluator, 8) else {
+                continue;
+            };
+
+            let solution = board.solve();
+            let expected = match solution.outcome {
+                Outcome::Win(player) => Score::win_in(player, solution.plies as u32),
+                Outcome::Tie => Score::DRAW,
+            };
+            assert_eq!(score, expected, "{board:?}");
+        }
+    }
+
+    /// A game that is not over, but where the side to move has nothing to play.
+    #[derive(Clone)]
+    struct Stuck;
+
+    impl Game for Stuck {
+        type Move = usize;
+
+        fn moves(&self) -> Vec<usize> {
+            Vec::new()
+        }
+
+        fn play_move(&self, _mv: usize) -> Option<Self> {
+            None
+        }
+
+        fn outcome(&self) -> Option<Outcome> {
+            None
+        }
+
+        fn side_to_move(&self) -> Player {
+            Player::X
+        }
+
+        fn hash_key(&self) -> u64 {
+            0
+        }
+    }
+
+    #[test]
+    fn finished_games_are_reported_as_over() {
+        // X has the top row
+        let board = ClassicBoard {
+            board: IndividualBoard(0b000_000_111, 0b000_011_000),
+            to_play: Player::O,
+        };
+        let over =
+            |result: BestMove<_>| matches!(result, BestMove::GameOver(Outcome::Win(Player::X)));
+
+        assert!(over(fixed_depth(&board, &OutcomeEvaluator, 3).map(|_| ())));
+        assert!(over(
+            iterative_deepening(
+                &board,
+                &OutcomeEvaluator,
+                &Budget::default(),
+                &TranspositionTable::with_entries(16),
+                &SearchOptions::default(),
+                |_| {},
+            )
+            .map(|_| ())
+        ));
+    }
+
+    #[test]
+    fn positions_without_moves_are_reported_as_stuck() {
+        assert!(matches!(
+            fixed_depth(&Stuck, &OutcomeEvaluator, 3),
+            BestMove::NoLegalMoves
+        ));
+        assert!(matches!(
+            iterative_deepening(
+                &Stuck,
+                &OutcomeEvaluator,
+                &Budget::default(),
+                &TranspositionTable::with_entries(16),
+                &SearchOptions::default(),
+                |_| {},
+            ),
+            BestMove::NoLegalMoves
+        ));
+    }
+
+    /// The best move and line of a depth 5 search of `board`.
+    fn search_line(
+        board: &Board,
+        table: &TranspositionTable<(usize, usize)>,
+        options: &SearchOptions,
+    ) -> ((usize, usize), Vec<(usize, usize)>) {
+        let budget = Budget {
+            max_depth: 5,
+            ..Budget::default()
+        };
+
+        let result = iterative_deepening(
+            board,
+            &TableEvaluator::default(),
+            &budget,
+            table,
+            options,
+            |_| {},
+        )
+        .best()
+        .expect("the opening has moves");
+
+        (result.best_move, result.pv)
+    }
+
+    #[test]
+    fn deterministic_searches_repeat_and_leave_the_table_alone() {
+        let board = random_opening(6, &mut Rng::new(3)).unwrap();
+        let options = SearchOptions {
+            deterministic: true,
+            ..SearchOptions::default()
+        };
+
+        let table = TranspositionTable::with_megabytes(1);
+        let first = search_line(&board, &table, &options);
+        assert!(table.probe(board.hash_key()).is_none());
+
+        // Whatever an ordinary search leaves behind must not change the result
+        search_line(
+            &board,
+            &table,
+            &SearchOptions {
+                threads: 2,
+                ..SearchOptions::default()
             },
-            depth,
         );
+        let stored = |table: &TranspositionTable<_>| {
+            table
+                .probe(board.hash_key())
+                .map(|entry| (entry.depth, entry.best_move))
+        };
+        let before = stored(&table);
+        assert!(before.is_some());
+
+        assert_eq!(search_line(&board, &table, &options), first);
+        assert_eq!(stored(&table), before);
     }
 
-    let range = if let Some(idx) = whole_board.global_idx {
-        idx * 9..idx * 9 + 9
-    } else {
-        0..81
-    };
+    #[test]
+    fn seeds_pick_between_equal_moves() {
+        // Every first move of classic tic-tac-toe draws, so only the seed decides between them
+        let best_move = |seed, threads| {
+            iterative_deepening(
+                &ClassicBoard::default(),
+                &OutcomeEvaluator,
+                &Budget {
+                    max_depth: 8,
+                    ..Budget::default()
+                },
+                &TranspositionTable::with_entries(1 << 12),
+                &SearchOptions {
+                    threads,
+                    seed: Some(seed),
+                    ..SearchOptions::default()
+                },
+                |_| {},
+            )
+            .best()
+            .unwrap()
+            .best_move
+        };
 
-    let alpha = AtomicF64::new(alpha);
-    let beta = AtomicF64::new(beta);
-
-    let results = range
-        .into_par_iter()
-        .filter(|idx| ((whole_board.locals_x | whole_board.locals_o) >> idx) & 1 == 0)
-        .map(|idx| (idx / 9, idx % 9))
-        .filter_map(|(global, local)| Some(((global, local), whole_board.play(global, local)?)))
-        .filter_map(|(pos, board)| {
-            if beta.load(atomic::Ordering::Relaxed) <= alpha.load(atomic::Ordering::Relaxed) {
-                return None;
+        let moves = (0..8)
+            .map(|seed| best_move(seed, 1))
+            .collect::<HashSet<_>>();
+        assert!(moves.len() > 1, "every seed picked {moves:?}");
+
+        for seed in 0..4 {
+            let first = best_move(seed, 1);
+            for _ in 0..3 {
+                assert_eq!(best_move(seed, 4), first, "seed {seed}");
             }
+        }
+    }
 
-            Some(if depth == 0 {
-                (pos, evaluate(whole_board), depth)
-            } else {
-                let alpha_val = alpha.load(atomic::Ordering::Relaxed);
-                let beta_val = beta.load(atomic::Ordering::Relaxed);
-                let (_, value, eval_depth) = if threaded_depth == 0 {
-                    minimax_single(&board, depth - 1, alpha_val, beta_val)
-                } else {
-                    minimax(&board, depth - 1, threaded_depth - 1, alpha_val, beta_val)
-                };
+    /// Searches an opening with `budget`, checking that the result is that of the last
+    /// iteration that finished.
+    fn search_within(budget: &Budget) -> SearchResult<(usize, usize)> {
+        let board = random_opening(4, &mut Rng::new(2)).unwrap();
+        let mut finished = Vec::new();
 
-                if whole_board.to_play == Player::X {
-                    if value > alpha_val {
-                        alpha.store(value, atomic::Ordering::Relaxed);
-                    }
-                } else {
-                    if value < beta_val {
-                        beta.store(value, atomic::Ordering::Relaxed);
-                    }
-                }
+        let result = iterative_deepening(
+            &board,
+            &TableEvaluator::default(),
+            budget,
+            &TranspositionTable::with_megabytes(1),
+            &SearchOptions {
+                threads: 1,
+                ..SearchOptions::default()
+            },
+            |result| finished.push((result.best_move, result.info.depth)),
+        )
+        .best()
+        .unwrap();
 
-                (pos, value, eval_depth)
-            })
+        assert_eq!(
+            finished.last(),
+            Some(&(result.best_move, result.info.depth))
+        );
+        result
+    }
+
+    #[test]
+    fn node_budget_stops_early_with_the_last_finished_iteration() {
+        let result = search_within(&Budget {
+            nodes: Some(20_000),
+            ..Budget::default()
         });
 
-    let res = if whole_board.to_play == Player::X {
-        results
-            .max_by(|(_, eval_a, depth_a), (_, eval_b, depth_b)| {
-                match eval_a.partial_cmp(eval_b).unwrap() {
-                    Ordering::Equal => depth_a.cmp(depth_b),
-                    n => n,
-                }
-            })
-            .unwrap()
-    } else {
-        results
-            .min_by(|(_, eval_a, depth_a), (_, eval_b, depth_b)| {
-                match eval_a.partial_cmp(eval_b).unwrap() {
-                    Ordering::Equal => depth_b.cmp(depth_a),
-                    n => n,
+        assert!(result.info.depth < Budget::default().max_depth);
+        // The iteration that ran out stops one node past the limit, on top of the first
+        // iteration, which always finishes
+        assert!(result.info.nodes <= 20_000 + 1 + 200);
+    }
+
+    #[test]
+    fn time_budget_stops_early_with_the_last_finished_iteration() {
+        let result = search_within(&Budget {
+            time: Some(Duration::from_millis(50)),
+            ..Budget::default()
+        });
+
+        assert!(result.info.depth < Budget::default().max_depth);
+        assert!(result.info.elapsed < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn stopping_from_another_thread_keeps_the_last_finished_iteration() {
+        let board = random_opening(4, &mut Rng::new(2)).unwrap();
+        let stop = StopHandle::new();
+        let (finished, stop_requests) = std::sync::mpsc::channel();
+
+        let stopper = std::thread::spawn({
+            let stop = stop.clone();
+            move || {
+                // Stop once the first iteration past depth 0 has finished
+                stop_requests.recv().unwrap();
+                stop.stop();
+            }
+        });
+
+        let mut last = None;
+        let result = iterative_deepening(
+            &board,
+            &TableEvaluator::default(),
+            &Budget {
+                stop: Some(stop),
+                ..Budget::default()
+            },
+            &TranspositionTable::with_megabytes(1),
+            &SearchOptions {
+                threads: 1,
+                ..SearchOptions::default()
+            },
+            |result| {
+                last = Some((result.best_move, result.info.depth));
+                if result.info.depth == 1 {
+                    finished.send(()).unwrap();
                 }
-            })
-            .unwrap()
-    };
+            },
+        )
+        .best()
+        .unwrap();
+        stopper.join().unwrap();
 
-    res
+        assert!((1..Budget::default().max_depth).contains(&result.info.depth));
+        assert_eq!(last, Some((result.best_move, result.info.depth)));
+        assert!(board.play_move(result.best_move).is_some());
+    }
 }
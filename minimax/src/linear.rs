@@ -0,0 +1,263 @@
+use std::{fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+use ultimate_tic_tac_toe::{Board, Player, WIN_LINES};
+
+use crate::{eval::Evaluator, selfplay::Sample};
+
+pub const FEATURES: usize = 5;
+
+pub const FEATURE_NAMES: [&str; FEATURES] = [
+    "won_boards",
+    "center_board",
+    "open_global_lines",
+    "open_local_twos",
+    "forced_send",
+];
+
+/// Counts the win lines holding at least `min_count` of `own` and none of `blocked`.
+fn open_lines(own: u16, blocked: u16, min_count: u32) -> f64 {
+    WIN_LINES
+        .into_iter()
+        .filter(|&line| blocked & line == 0 && (own & line).count_ones() >= min_count)
+        .count() as f64
+}
+
+/// The feature values of a position, X's count minus O's count, in the order of
+/// [`FEATURE_NAMES`].
+pub fn features(board: &Board) -> [f64; FEATURES] {
+    let decided = board.global_x | board.global_o | board.global_full;
+
+    let won_boards = board.global_x.count_ones() as f64 - board.global_o.count_ones() as f64;
+
+    let center_board = if (board.global_x >> 4) & 1 == 1 {
+        1.0
+    } else if (board.global_o >> 4) & 1 == 1 {
+        -1.0
+    } else {
+        let center = board.get_local(4);
+        (center.0.count_ones() as f64 - center.1.count_ones() as f64) / 9.0
+    };
+
+    let open_global_lines = open_lines(board.global_x, board.global_o | board.global_full, 1)
+        - open_lines(board.global_o, board.global_x | board.global_full, 1);
+
+    let open_local_twos = (0..9)
+        .filter(|idx| (decided >> idx) & 1 == 0)
+        .map(|idx| {
+            let local = board.get_local(idx);
+            open_lines(local.0, local.1, 2) - open_lines(local.1, local.0, 2)
+        })
+        .sum::<f64>();
+
+    // A free move is a gift from the side that just moved
+    let forced_send = match (board.global_idx, board.to_play) {
+        (Some(_), _) => 0.0,
+        (None, Player::X) => 1.0,
+        (None, Player::O) => -1.0,
+    };
+
+    [
+        won_boards,
+        center_board,
+        open_global_lines,
+        open_local_twos,
+        forced_send,
+    ]
+}
+
+/// The weight of each feature in [`LinearEvaluator`], stored as JSON by the tuner.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Weights {
+    pub won_boards: f64,
+    pub center_board: f64,
+    pub open_global_lines: f64,
+    pub open_local_twos: f64,
+    pub forced_send: f64,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Weights {
+            won_boards: 0.3,
+            center_board: 0.2,
+            open_global_lines: 0.1,
+            open_local_twos: 0.05,
+            forced_send: 0.1,
+        }
+    }
+}
+
+impl Weights {
+    pub fn to_array(self) -> [f64; FEATURES] {
+        [
+            self.won_boards,
+            self.center_board,
+            self.open_global_lines,
+            self.open_local_twos,
+            self.forced_send,
+        ]
+    }
+
+    pub fn from_array(
+        [won_boards, center_board, open_global_lines, open_local_twos, forced_send]: [f64;
+            FEATURES],
+    ) -> Self {
+        Weights {
+            won_boards,
+            center_board,
+            open_global_lines,
+            open_local_twos,
+            forced_send,
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// A weighted sum of hand picked [`features`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LinearEvaluator {
+    pub weights: Weights,
+}
+
+impl LinearEvaluator {
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Ok(LinearEvaluator {
+            weights: Weights::load(path)?,
+        })
+    }
+}
+
+fn dot(weights: &[f64; FEATURES], features: &[f64; FEATURES]) -> f64 {
+    weights.iter().zip(features).map(|(a, b)| a * b).sum()
+}
+
+impl Evaluator<Board> for LinearEvaluator {
+    fn evaluate(&self, game: &Board) -> f64 {
+        dot(&self.weights.to_array(), &features(game))
+    }
+}
+
+crate::eval::evaluate_inside_pie_rule!(LinearEvaluator);
+
+#[derive(Clone, Debug)]
+pub struct TunerConfig {
+    pub iterations: usize,
+    pub learning_rate: f64,
+    /// Steepness of the sigmoid mapping an evaluation to an expected result.
+    pub scale: f64,
+}
+
+impl Default for TunerConfig {
+    fn default() -> Self {
+        TunerConfig {
+            iterations: 1000,
+            learning_rate: 1.0,
+            scale: 1.0,
+        }
+    }
+}
+
+fn sigmoid(x: f64) -> f64 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+/// Mean squared difference between each result and the result predicted from its evaluation.
+pub fn mean_error(samples: &[Sample], weights: Weights, scale: f64) -> f64 {
+    let weights = weights.to_array();
+
+    samples
+        .iter()
+        .map(|sample| {
+            let predicted = sigmoid(scale * dot(&weights, &features(&sample.board)));
+            (predicted - sample.result).powi(2)
+        })
+        .sum::<f64>()
+        / samples.len() as f64
+}
+
+/// Fits the weights to game results Texel style, by gradient descent on [`mean_error`].
+pub fn tune(samples: &[Sample], initial: Weights, config: &TunerConfig) -> Weights {
+    let data = samples
+        .iter()
+        .map(|sample| (features(&sample.board), sample.result))
+        .collect::<Vec<_>>();
+
+    if data.is_empty() {
+        return initial;
+    }
+
+    let mut weights = initial.to_array();
+
+    for _ in 0..config.iterations {
+        let mut gradient = [0.0; FEATURES];
+
+        for (features, result) in &data {
+            let predicted = sigmoid(config.scale * dot(&weights, features));
+            let slope = 2.0 * (predicted - result) * predicted * (1.0 - predicted) * config.scale;
+
+            for (gradient, feature) in gradient.iter_mut().zip(features) {
+                *gradient += slope * feature;
+            }
+        }
+
+        for (weight, gradient) in weights.iter_mut().zip(gradient) {
+            *weight -= config.learning_rate * gradient / data.len() as f64;
+        }
+    }
+
+    Weights::from_array(weights)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{openings::random_opening, rng::Rng};
+
+    /// Random positions labelled with the result a known set of weights predicts for them.
+    fn synthetic_samples(weights: Weights) -> Vec<Sample> {
+        let mut rng = Rng::new(7);
+
+        (0..200)
+            .filter_map(|idx| random_opening(4 + idx % 30, &mut rng))
+            .map(|board| {
+                let result = sigmoid(dot(&weights.to_array(), &features(&board)));
+                Sample { board, result }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn tuning_lowers_the_error() {
+        let samples = synthetic_samples(Weights::default());
+        let initial = Weights::from_array([0.0; FEATURES]);
+        let config = TunerConfig {
+            iterations: 200,
+            ..TunerConfig::default()
+        };
+
+        let tuned = tune(&samples, initial, &config);
+
+        assert!(mean_error(&samples, tuned, 1.0) < mean_error(&samples, initial, 1.0));
+    }
+
+    #[test]
+    fn weights_round_trip_through_a_file() {
+        let weights = Weights::from_array([0.125, -0.5, 1.0 / 3.0, 0.0, 2.5]);
+        let path = std::env::temp_dir().join(format!("linear-weights-{}.json", std::process::id()));
+
+        weights.save(&path).unwrap();
+        let loaded = Weights::load(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.unwrap(), weights);
+    }
+}
@@ -0,0 +1,450 @@
+//! Monte Carlo tree search. Instead of evaluating positions it plays random games from them,
+//! growing a tree towards the moves that win those games most often and picking between
+//! promising and little tried moves with UCT.
+
+use std::{
+    sync::{
+        atomic::{self, AtomicU64},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use ultimate_tic_tac_toe::{Game, Outcome, Player};
+
+use crate::{available_threads, rng::Rng, BestMove};
+
+/// Playouts per search when neither the playouts nor the time are limited.
+pub const DEFAULT_PLAYOUTS: u64 = 100_000;
+
+#[derive(Clone, Debug)]
+pub struct MctsConfig {
+    /// Playouts per search, not counting those kept from earlier searches. Without this or
+    /// `time`, a search runs [`DEFAULT_PLAYOUTS`].
+    pub playouts: Option<u64>,
+    pub time: Option<Duration>,
+    /// Once the tree has this many nodes it stops growing, and playouts start from the leaves
+    /// it already has.
+    pub max_nodes: usize,
+    /// Threads running playouts at once, all growing the same tree.
+    pub threads: usize,
+    /// How much UCT favours moves that have been tried less over moves that have won more.
+    pub exploration: f64,
+    pub seed: u64,
+}
+
+impl Default for MctsConfig {
+    fn default() -> Self {
+        MctsConfig {
+            playouts: Some(DEFAULT_PLAYOUTS),
+            time: None,
+            max_nodes: 1_000_000,
+            threads: available_threads(),
+            exploration: std::f64::consts::SQRT_2,
+            seed: 0,
+        }
+    }
+}
+
+/// What [`Mcts::search`] found.
+#[derive(Clone, Debug)]
+pub struct MctsResult<M> {
+    /// The move with the most playouts.
+    pub best_move: M,
+    /// The share of playouts through `best_move` won by the side to move, ties counting half.
+    pub win_rate: f64,
+    /// Playouts through the root, including those kept from earlier searches.
+    pub visits: u64,
+    /// Playouts run by this search.
+    pub playouts: u64,
+    pub elapsed: Duration,
+}
+
+struct Node<G: Game> {
+    game: G,
+    /// The move that led here from the parent, `None` at the root.
+    mv: Option<G::Move>,
+    children: Vec<usize>,
+    /// Legal moves without a child yet.
+    untried: Vec<G::Move>,
+    /// The player whose move led here, who the rewards are counted for.
+    mover: Player,
+    visits: u64,
+    /// 1 for every playout through here `mover` won and 0.5 for every tie.
+    reward: f64,
+}
+
+impl<G: Game> Node<G> {
+    fn new(game: G, mv: Option<G::Move>, mover: Player) -> Self {
+        Node {
+            untried: game.moves(),
+            game,
+            mv,
+            children: Vec::new(),
+            mover,
+            visits: 0,
+            reward: 0.0,
+        }
+    }
+}
+
+/// The nodes of one tree, with the root first.
+struct Tree<G: Game> {
+    nodes: Vec<Node<G>>,
+}
+
+impl<G: Game> Tree<G> {
+    fn new(game: G) -> Self {
+        let mover = game.side_to_move().invert();
+
+        Tree {
+            nodes: vec![Node::new(game, None, mover)],
+        }
+    }
+
+    /// The child of `parent` with the best UCT value.
+    fn select_child(&self, parent: usize, exploration: f64) -> usize {
+        let log_visits = (self.nodes[parent].visits as f64).ln();
+
+        let uct = |&child: &usize| {
+            let node = &self.nodes[child];
+            let visits = node.visits as f64;
+
+            node.reward / visits + exploration * (log_visits / visits).sqrt()
+        };
+
+        *self.nodes[parent]
+            .children
+            .iter()
+            .max_by(|a, b| uct(a).total_cmp(&uct(b)))
+            .expect("only called on nodes with children")
+    }
+
+    /// Walks down from the root to a node to play out from, adding a child for a move not tried
+    /// yet on the way if there is one and the tree may `grow`. Every node on the path counts
+    /// the playout as a loss until [`Tree::backpropagate`] adds its result, which steers other
+    /// threads to other paths meanwhile.
+    fn select(&mut self, rng: &mut Rng, exploration: f64, grow: bool) -> Vec<usize> {
+        let mut path = vec![0];
+        let mut node = 0;
+        self.nodes[node].visits += 1;
+
+        loop {
+            while grow && !self.nodes[node].untried.is_empty() {
+                let untried = &mut self.nodes[node].untried;
+                let mv = untried.swap_remove(rng.below(untried.len()));
+
+                let parent = &self.nodes[node];
+                if let Some(game) = parent.game.play_move(mv) {
+                    let child = Node::new(game, Some(mv), parent.game.side_to_move());
+                    let idx = self.nodes.len();
+
+                    self.nodes.push(child);
+                    self.nodes[node].children.push(idx);
+                    self.nodes[idx].visits += 1;
+                    path.push(idx);
+
+                    return path;
+                }
+            }
+
+            if self.nodes[node].children.is_empty() {
+                return path;
+            }
+
+            node = self.select_child(node, exploration);
+            self.nodes[node].visits += 1;
+            path.push(node);
+        }
+    }
+
+    /// Adds the result of a playout to every node on its path.
+    fn backpropagate(&mut self, path: &[usize], outcome: Outcome) {
+        for &idx in path {
+            let node = &mut self.nodes[idx];
+
+            node.reward += match outcome {
+                Outcome::Win(player) if player == node.mover => 1.0,
+                Outcome::Win(_) => 0.0,
+                Outcome::Tie => 0.5,
+            };
+        }
+    }
+
+    /// The subtree of the node for `game`, if it is the root, a child or a grandchild, so a
+    /// search can go on from what the last one learned after a move from each side. Every
+    /// node outside the subtree is dropped.
+    fn reroot(mut self, game: &G) -> Option<Self> {
+        let key = game.hash_key();
+
+        let children = &self.nodes[0].children;
+        let root = std::iter::once(0)
+            .chain(children.iter().copied())
+            .chain(
+                children
+                    .iter()
+                    .flat_map(|&child| self.nodes[child].children.iter().copied()),
+            )
+            .find(|&idx| self.nodes[idx].game.hash_key() == key)?;
+
+        let mut nodes = Vec::new();
+        let mut stack = vec![(root, None)];
+
+        while let Some((idx, parent)) = stack.pop() {
+            let new_idx = nodes.len();
+            let node = &mut self.nodes[idx];
+
+            stack.extend(node.children.iter().map(|&child| (child, Some(new_idx))));
+            if let Some(parent) = parent {
+                let parent: &mut Node<G> = &mut nodes[parent];
+                parent.children.push(new_idx);
+            }
+
+            nodes.push(Node {
+                game: node.game.clone(),
+                mv: node.mv,
+                children: Vec::new(),
+                untried: std::mem::take(&mut node.untried),
+                mover: node.mover,
+                visits: node.visits,
+                reward: node.reward,
+            });
+        }
+
+        nodes[0].mv = None;
+
+        Some(Tree { nodes })
+    }
+}
+
+/// Plays uniformly random moves from `game` until the game ends. A position without a legal
+/// move counts as a tie.
+fn playout<G: Game>(game: &G, rng: &mut Rng) -> Outcome {
+    let mut game = game.clone();
+
+    loop {
+        if let Some(outcome) = game.outcome() {
+            return outcome;
+        }
+
+        let moves = game.moves();
+        let Some(next) = (!moves.is_empty())
+            .then(|| game.play_move(moves[rng.below(moves.len())]))
+            .flatten()
+        else {
+            return Outcome::Tie;
+        };
+
+        game = next;
+    }
+}
+
+/// A Monte Carlo tree search engine that keeps its tree between searches, so a search for the
+/// position after a move from each side starts from the playouts already run through it.
+pub struct Mcts<G: Game> {
+    pub config: MctsConfig,
+    tree: Option<Tree<G>>,
+    searches: u64,
+}
+
+impl<G: Game> Mcts<G> {
+    pub fn new(config: MctsConfig) -> Self {
+        Mcts {
+            config,
+            tree: None,
+            searches: 0,
+        }
+    }
+
+    /// Forgets the tree, so the next search starts from nothing.
+    pub fn clear(&mut self) {
+        self.tree = None;
+    }
+
+    /// Runs playouts from `game` until the budget runs out. The first playout always runs, so
+    /// there is a move even with an empty budget as long as the game has one.
+    pub fn search(&mut self, game: &G) -> BestMove<MctsResult<G::Move>> {
+        let start = Instant::now();
+
+        if let Some(outcome) = game.outcome() {
+            return BestMove::GameOver(outcome);
+        }
+
+        let tree = self
+            .tree
+            .take()
+            .and_then(|tree| tree.reroot(game))
+            .unwrap_or_else(|| Tree::new(game.clone()));
+
+        let deadline = self.config.time.map(|time| start + time);
+        let playout_limit = match (self.config.playouts, deadline) {
+            (None, None) => Some(DEFAULT_PLAYOUTS),
+            (limit, _) => limit,
+        };
+        let started = AtomicU64::new(0);
+        let finished = AtomicU64::new(0);
+        let tree = Mutex::new(tree);
+
+        std::thread::scope(|scope| {
+            for thread in 0..self.config.threads.max(1) as u64 {
+                let (tree, started, finished) = (&tree, &started, &finished);
+                let config = &self.config;
+                // A new stream for every search, or reusing the tree would replay the same games
+                let mut rng = Rng::new(config.seed ^ (self.searches << 32) ^ thread);
+
+                scope.spawn(move || loop {
+                    let count = started.fetch_add(1, atomic::Ordering::Relaxed);
+                    let out_of_playouts = playout_limit.is_some_and(|limit| count >= limit);
+                    let out_of_time = deadline.is_some_and(|deadline| Instant::now() >= deadline);
+                    if count > 0 && (out_of_playouts || out_of_time) {
+                        break;
+                    }
+
+                    let (path, leaf) = {
+                        let mut tree = tree.lock().unwrap();
+                        let grow = tree.nodes.len() < config.max_nodes;
+                        let path = tree.select(&mut rng, config.exploration, grow);
+                        let leaf = tree.nodes[*path.last().unwrap()].game.clone();
+                        (path, leaf)
+                    };
+
+                    let outcome = playout(&leaf, &mut rng);
+                    tree.lock().unwrap().backpropagate(&path, outcome);
+                    finished.fetch_add(1, atomic::Ordering::Relaxed);
+                });
+            }
+        });
+
+        let tree = tree.into_inner().unwrap();
+        let playouts = finished.into_inner();
+
+        let root = &tree.nodes[0];
+        let best = root
+            .children
+            .iter()
+            .map(|&child| &tree.nodes[child])
+            .max_by_key(|child| child.visits)
+            .map(|child| MctsResult {
+                best_move: child.mv.expect("only the root has no move"),
+                win_rate: child.reward / child.visits as f64,
+                visits: root.visits,
+                playouts,
+                elapsed: start.elapsed(),
+            });
+
+        self.searches += 1;
+        self.tree = Some(tree);
+
+        match best {
+            Some(best) => BestMove::Best(best),
+            None => BestMove::NoLegalMoves,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ultimate_tic_tac_toe::{classic::ClassicBoard, Board, IndividualBoard};
+
+    use super::*;
+
+    fn config(playouts: u64) -> MctsConfig {
+        MctsConfig {
+            playouts: Some(playouts),
+            threads: 1,
+            ..MctsConfig::default()
+        }
+    }
+
+    #[test]
+    fn classic_immediate_win_is_found() {
+        // X holds the first two squares of the top row and O two of the middle row
+        let board = ClassicBoard {
+            board: IndividualBoard(0b000_000_011, 0b000_011_000),
+            to_play: Player::X,
+        };
+
+        let result = Mcts::new(config(2000)).search(&board).best().unwrap();
+
+        assert_eq!(result.best_move, 2);
+    }
+
+    #[test]
+    fn ultimate_winning_move_is_found() {
+        // Plays random games until one reaches a position with a move that wins it
+        let mut rng = Rng::new(7);
+        let board = std::iter::repeat_with(|| {
+            let mut board = Board::default();
+            while board.outcome().is_none() {
+                let moves = board.moves();
+                let wins = moves.iter().any(|&mv| {
+                    board.play_move(mv).unwrap().outcome() == Some(Outcome::Win(board.to_play))
+                });
+                if wins {
+                    return Some(board);
+                }
+                board = board.play_move(moves[rng.below(moves.len())])?;
+            }
+            None
+        })
+        .flatten()
+        .next()
+        .unwrap();
+
+        let result = Mcts::new(config(5000)).search(&board).best().unwrap();
+        let after = board.play_move(result.best_move).unwrap();
+
+        assert_eq!(after.outcome(), Some(Outcome::Win(board.to_play)));
+    }
+
+    #[test]
+    fn reroot_keeps_visits_after_a_move_from_each_side() {
+        let board = Board::default();
+        let mut mcts = Mcts::new(config(5000));
+        mcts.search(&board);
+
+        // The position the search saw most of after its own move and the most likely reply
+        let tree = mcts.tree.as_ref().unwrap();
+        let most_visited = |node: usize| {
+            *tree.nodes[node]
+                .children
+                .iter()
+                .max_by_key(|&&child| tree.nodes[child].visits)
+                .unwrap()
+        };
+        let grandchild = &tree.nodes[most_visited(most_visited(0))];
+        let (position, visits) = (grandchild.game.clone(), grandchild.visits);
+        assert!(visits > 0);
+
+        let result = mcts.search(&position).best().unwrap();
+
+        assert_eq!(result.visits, visits + result.playouts);
+    }
+
+    #[test]
+    fn search_without_a_budget_runs_the_default_playouts() {
+        let mut mcts = Mcts::new(MctsConfig {
+            playouts: None,
+            time: None,
+            threads: 1,
+            ..MctsConfig::default()
+        });
+
+        let result = mcts.search(&ClassicBoard::default()).best().unwrap();
+
+        assert_eq!(result.playouts, DEFAULT_PLAYOUTS);
+    }
+
+    #[test]
+    fn tree_stops_growing_at_the_node_limit() {
+        let mut mcts = Mcts::new(MctsConfig {
+            max_nodes: 100,
+            ..config(2000)
+        });
+
+        let result = mcts.search(&Board::default()).best().unwrap();
+
+        assert_eq!(result.playouts, 2000);
+        assert_eq!(mcts.tree.unwrap().nodes.len(), 100);
+    }
+}
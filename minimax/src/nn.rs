@@ -0,0 +1,278 @@
+//! A tiny multilayer perceptron evaluation that runs on the CPU without any dependencies.
+//!
+//! Networks are stored as JSON holding the hidden layer size and the weights of both layers.
+
+use std::{fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+use ultimate_tic_tac_toe::{Board, Player};
+
+use crate::{eval::Evaluator, rng::Rng, selfplay::Sample};
+
+/// X squares, O squares, boards won by X, won by O and tied, the forced board and the side to move.
+pub const INPUTS: usize = 81 + 81 + 9 + 9 + 9 + 9 + 1;
+
+/// The input planes of a position, one value per input.
+pub fn encode(board: &Board) -> [f32; INPUTS] {
+    let mut input = [0.0; INPUTS];
+
+    let planes = [
+        (0, board.locals_x, 81),
+        (81, board.locals_o, 81),
+        (162, board.global_x as u128, 9),
+        (171, board.global_o as u128, 9),
+        (180, board.global_full as u128, 9),
+    ];
+
+    for (offset, bits, len) in planes {
+        for idx in 0..len {
+            input[offset + idx] = ((bits >> idx) & 1) as f32;
+        }
+    }
+
+    if let Some(idx) = board.global_idx {
+        input[189 + idx] = 1.0;
+    }
+
+    if board.to_play == Player::X {
+        input[198] = 1.0;
+    }
+
+    input
+}
+
+/// One hidden ReLU layer feeding a single tanh output, positive when X is ahead.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Network {
+    pub hidden: usize,
+    /// `hidden` rows of [`INPUTS`] weights.
+    pub hidden_weights: Vec<f32>,
+    pub hidden_biases: Vec<f32>,
+    pub output_weights: Vec<f32>,
+    pub output_bias: f32,
+}
+
+impl Network {
+    /// A network with small random weights.
+    pub fn new(hidden: usize, rng: &mut Rng) -> Self {
+        let mut uniform = |scale: f32| (rng.next_f64() as f32 * 2.0 - 1.0) * scale;
+
+        let hidden_scale = (1.0 / INPUTS as f32).sqrt();
+        let output_scale = (1.0 / hidden as f32).sqrt();
+
+        Network {
+            hidden,
+            hidden_weights: (0..hidden * INPUTS)
+                .map(|_| uniform(hidden_scale))
+                .collect(),
+            hidden_biases: vec![0.0; hidden],
+            output_weights: (0..hidden).map(|_| uniform(output_scale)).collect(),
+            output_bias: 0.0,
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let network: Network = serde_json::from_str(&fs::read_to_string(path)?)?;
+
+        anyhow::ensure!(
+            network.hidden_weights.len() == network.hidden * INPUTS
+                && network.hidden_biases.len() == network.hidden
+                && network.output_weights.len() == network.hidden,
+            "Network weights do not match a hidden layer of {}",
+            network.hidden
+        );
+
+        Ok(network)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    fn hidden_layer(&self, input: &[f32; INPUTS]) -> Vec<f32> {
+        self.hidden_weights
+            .chunks_exact(INPUTS)
+            .zip(&self.hidden_biases)
+            .map(|(weights, bias)| {
+                let sum = weights.iter().zip(input).map(|(w, x)| w * x).sum::<f32>() + bias;
+                sum.max(0.0)
+            })
+            .collect()
+    }
+
+    fn output(&self, hidden: &[f32]) -> f32 {
+        let sum = self
+            .output_weights
+            .iter()
+            .zip(hidden)
+            .map(|(w, x)| w * x)
+            .sum::<f32>();
+
+        (sum + self.output_bias).tanh()
+    }
+
+    pub fn forward(&self, board: &Board) -> f32 {
+        self.output(&self.hidden_layer(&encode(board)))
+    }
+
+    /// One step of gradient descent on the squared error against `target`, returning the error.
+    fn train_step(&mut self, input: &[f32; INPUTS], target: f32, learning_rate: f32) -> f32 {
+        let hidden = self.hidden_layer(input);
+        let output = self.output(&hidden);
+
+        let error = output - target;
+        let output_grad = 2.0 * error * (1.0 - output * output);
+
+        for (idx, &activation) in hidden.iter().enumerate() {
+            let hidden_grad = if activation > 0.0 {
+                output_grad * self.output_weights[idx]
+            } else {
+                0.0
+            };
+
+            self.output_weights[idx] -= learning_rate * output_grad * activation;
+
+            if hidden_grad != 0.0 {
+                let weights = &mut self.hidden_weights[idx * INPUTS..(idx + 1) * INPUTS];
+
+                for (weight, x) in weights.iter_mut().zip(input) {
+                    *weight -= learning_rate * hidden_grad * x;
+                }
+                self.hidden_biases[idx] -= learning_rate * hidden_grad;
+            }
+        }
+
+        self.output_bias -= learning_rate * output_grad;
+
+        error * error
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TrainConfig {
+    pub epochs: usize,
+    pub learning_rate: f32,
+}
+
+impl Default for TrainConfig {
+    fn default() -> Self {
+        TrainConfig {
+            epochs: 10,
+            learning_rate: 0.01,
+        }
+    }
+}
+
+/// Trains on self-play samples with stochastic gradient descent, calling `on_epoch` with the
+/// epoch number and the mean squared error of that epoch.
+pub fn train(
+    network: &mut Network,
+    samples: &[Sample],
+    config: &TrainConfig,
+    rng: &mut Rng,
+    mut on_epoch: impl FnMut(usize, f32),
+) {
+    let data = samples
+        .iter()
+        .map(|sample| (encode(&sample.board), sample.result as f32 * 2.0 - 1.0))
+        .collect::<Vec<_>>();
+
+    if data.is_empty() {
+        return;
+    }
+
+    let mut order = (0..data.len()).collect::<Vec<_>>();
+
+    for epoch in 0..config.epochs {
+        for idx in (1..order.len()).rev() {
+            order.swap(idx, rng.below(idx + 1));
+        }
+
+        let error = order
+            .iter()
+            .map(|&idx| {
+                let (input, target) = &data[idx];
+                network.train_step(input, *target, config.learning_rate)
+            })
+            .sum::<f32>()
+            / data.len() as f32;
+
+        on_epoch(epoch, error);
+    }
+}
+
+/// Evaluates positions with a trained [`Network`].
+#[derive(Clone, Debug)]
+pub struct NeuralEvaluator {
+    pub network: Network,
+}
+
+impl NeuralEvaluator {
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Ok(NeuralEvaluator {
+            network: Network::load(path)?,
+        })
+    }
+}
+
+impl Evaluator<Board> for NeuralEvaluator {
+    fn evaluate(&self, game: &Board) -> f64 {
+        self.network.forward(game) as f64
+    }
+}
+
+crate::eval::evaluate_inside_pie_rule!(NeuralEvaluator);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::openings::random_opening;
+
+    /// Mean squared error of the network over `samples`, with results scaled like [`train`].
+    fn error(network: &Network, samples: &[Sample]) -> f32 {
+        samples
+            .iter()
+            .map(|sample| {
+                (network.forward(&sample.board) - (sample.result as f32 * 2.0 - 1.0)).powi(2)
+            })
+            .sum::<f32>()
+            / samples.len() as f32
+    }
+
+    #[test]
+    fn training_lowers_the_error() {
+        let mut rng = Rng::new(3);
+        let samples = (0..20)
+            .filter_map(|idx| random_opening(6 + idx, &mut rng))
+            .enumerate()
+            .map(|(idx, board)| Sample {
+                board,
+                result: (idx % 3) as f64 / 2.0,
+            })
+            .collect::<Vec<_>>();
+
+        let mut network = Network::new(16, &mut rng);
+        let before = error(&network, &samples);
+
+        let config = TrainConfig {
+            epochs: 50,
+            ..TrainConfig::default()
+        };
+        train(&mut network, &samples, &config, &mut rng, |_, _| {});
+
+        assert!(error(&network, &samples) < before);
+    }
+
+    #[test]
+    fn saved_weights_load_back_identically() {
+        let network = Network::new(8, &mut Rng::new(5));
+        let path = std::env::temp_dir().join(format!("network-{}.json", std::process::id()));
+
+        network.save(&path).unwrap();
+        let loaded = Network::load(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.unwrap(), network);
+    }
+}
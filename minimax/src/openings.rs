@@ -0,0 +1,139 @@
+use std::{
+    collections::HashSet,
+    io::{BufRead, Write},
+};
+
+use ultimate_tic_tac_toe::{Board, Game, Score};
+
+use crate::{eval::TableEvaluator, fixed_depth, rng::Rng};
+
+#[derive(Clone, Debug)]
+pub struct OpeningConfig {
+    /// Number of random moves played from the empty board.
+    pub plies: usize,
+    pub count: usize,
+    /// Depth of the search used to judge each opening.
+    pub depth: u64,
+    /// Openings whose evaluation is further than this from zero are thrown away.
+    pub max_eval: f64,
+    /// Give up after this many random openings have been tried.
+    pub max_attempts: usize,
+}
+
+impl Default for OpeningConfig {
+    fn default() -> Self {
+        OpeningConfig {
+            plies: 4,
+            count: 50,
+            depth: 6,
+            max_eval: 0.05,
+            max_attempts: 100_000,
+        }
+    }
+}
+
+/// Plays `plies` uniformly random moves from the empty board, `None` if the game ends first.
+pub fn random_opening(plies: usize, rng: &mut Rng) -> Option<Board> {
+    (0..plies).try_fold(Board::default(), |board, _| {
+        let moves = board.moves();
+
+        if moves.is_empty() {
+            return None;
+        }
+
+        board.play_move(moves[rng.below(moves.len())])
+    })
+}
+
+/// Plays random openings and keeps the distinct ones the engine considers roughly balanced,
+/// along with their evaluations.
+pub fn generate_openings(config: &OpeningConfig, rng: &mut Rng) -> Vec<(Board, Score)> {
+    let mut seen = HashSet::new();
+    let mut openings = Vec::new();
+
+    for _ in 0..config.max_attempts {
+        if openings.len() >= config.count {
+            break;
+        }
+
+        let Some(board) = random_opening(config.plies, rng) else {
+            continue;
+        };
+
+        if board.outcome().is_some() || !seen.insert(board.clone()) {
+            continue;
+        }
+
+        let Some((_, score)) = fixed_depth(&board, &TableEvaluator::default(), config.depth).best()
+        else {
+            continue;
+        };
+
+        if score.to_eval().abs() <= config.max_eval {
+            openings.push((board, score));
+        }
+    }
+
+    openings
+}
+
+/// Writes one position string per line.
+pub fn write_openings<'a>(
+    openings: impl IntoIterator<Item = &'a Board>,
+    mut writer: impl Write,
+) -> std::io::Result<()> {
+    for board in openings {
+        writeln!(writer, "{}", board.position_string())?;
+    }
+
+    Ok(())
+}
+
+/// Reads position strings written by [`write_openings`], skipping blank lines and `#` comments.
+pub fn read_openings(reader: impl BufRead) -> anyhow::Result<Vec<Board>> {
+    let mut openings = Vec::new();
+
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        openings.push(line.parse()?);
+    }
+
+    Ok(openings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_openings_are_legal_balanced_and_round_trip() {
+        let config = OpeningConfig {
+            count: 5,
+            depth: 2,
+            ..OpeningConfig::default()
+        };
+        let openings = generate_openings(&config, &mut Rng::new(1));
+        assert_eq!(openings.len(), config.count);
+
+        for (board, score) in &openings {
+            let marks = (board.locals_x | board.locals_o).count_ones() as usize;
+            assert_eq!(marks, config.plies);
+            assert!(board.outcome().is_none());
+            assert!(!board.moves().is_empty());
+            assert!(score.to_eval().abs() <= config.max_eval);
+        }
+
+        let boards = openings.iter().map(|(board, _)| board).collect::<Vec<_>>();
+        let mut written = Vec::new();
+        write_openings(boards.iter().copied(), &mut written).unwrap();
+
+        let read = read_openings(written.as_slice()).unwrap();
+        assert_eq!(read.iter().collect::<Vec<_>>(), boards);
+    }
+}
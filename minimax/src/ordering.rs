@@ -0,0 +1,129 @@
+//! The order the search tries moves in. Every order reaches the same score, but trying the best
+//! move first lets alpha-beta prune more of the others.
+
+use dashmap::DashMap;
+use ultimate_tic_tac_toe::Game;
+
+use crate::rng::Rng;
+
+/// Which heuristics decide the move order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MoveOrdering {
+    /// The best move stored in the transposition table by an earlier search.
+    pub hash_move: bool,
+    /// Moves that caused a cutoff elsewhere at the same ply.
+    pub killers: bool,
+    /// Moves that caused cutoffs anywhere, weighted by how deep the search below them was.
+    pub history: bool,
+    /// [`Game::move_priority`] for the rest.
+    pub priority: bool,
+}
+
+impl MoveOrdering {
+    /// The moves in the order the game generates them.
+    pub const NONE: MoveOrdering = MoveOrdering {
+        hash_move: false,
+        killers: false,
+        history: false,
+        priority: false,
+    };
+}
+
+impl Default for MoveOrdering {
+    fn default() -> Self {
+        MoveOrdering {
+            hash_move: true,
+            killers: true,
+            history: true,
+            priority: true,
+        }
+    }
+}
+
+/// What one search has learned about good moves so far.
+pub(crate) struct OrderingState<M> {
+    ordering: MoveOrdering,
+    /// Shuffles the moves at the root instead, see [`crate::SearchOptions::seed`].
+    seed: Option<u64>,
+    killers: DashMap<u32, [Option<M>; 2]>,
+    history: DashMap<M, u64>,
+}
+
+impl<M: Copy + Eq + std::hash::Hash> OrderingState<M> {
+    pub(crate) fn new(ordering: MoveOrdering, seed: Option<u64>) -> Self {
+        OrderingState {
+            ordering,
+            seed,
+            killers: DashMap::new(),
+            history: DashMap::new(),
+        }
+    }
+
+    /// The moves of `game` at `ply`, best first.
+    pub(crate) fn order<G: Game<Move = M>>(
+        &self,
+        game: &G,
+        hash_move: Option<M>,
+        ply: u32,
+    ) -> Vec<M> {
+        let mut moves = game.moves();
+
+        let hash_move = hash_move.filter(|_| self.ordering.hash_move);
+
+        // Of equally good moves the one searched first is kept, so a shuffle picks among them
+        if let (0, Some(seed)) = (ply, self.seed) {
+            let mut rng = Rng::new(seed);
+            for idx in (1..moves.len()).rev() {
+                moves.swap(idx, rng.below(idx + 1));
+            }
+
+            if let Some(idx) = moves.iter().position(|&mv| Some(mv) == hash_move) {
+                moves[..=idx].rotate_right(1);
+            }
+
+            return moves;
+        }
+
+        let killers = self
+            .killers
+            .get(&ply)
+            .filter(|_| self.ordering.killers)
+            .map_or([None; 2], |killers| *killers);
+
+        moves.sort_by_cached_key(|&mv| {
+            let history = match self.ordering.history {
+                true => self.history.get(&mv).map_or(0, |count| *count),
+                false => 0,
+            };
+            let priority = match self.ordering.priority {
+                true => game.move_priority(mv),
+                false => 0,
+            };
+
+            std::cmp::Reverse((
+                Some(mv) == hash_move,
+                killers.contains(&Some(mv)),
+                history,
+                priority,
+            ))
+        });
+
+        moves
+    }
+
+    /// Remembers that `mv` was good enough to cut off a search `depth` plies deep at `ply`.
+    pub(crate) fn record_cutoff(&self, mv: M, depth: u64, ply: u32) {
+        if self.ordering.killers {
+            let mut killers = self.killers.entry(ply).or_insert([None; 2]);
+
+            if killers[0] != Some(mv) {
+                killers[1] = killers[0];
+                killers[0] = Some(mv);
+            }
+        }
+
+        if self.ordering.history {
+            *self.history.entry(mv).or_insert(0) += (depth + 1) * (depth + 1);
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use std::io::{BufRead, Write};
+
+use anyhow::Context;
+use ultimate_tic_tac_toe::{Board, Game, Outcome, Player};
+
+use crate::{eval::Evaluator, fixed_depth, rng::Rng, BestMove};
+
+/// A position from a finished game with the result of that game for X: 1.0 for a win, 0.5 for a
+/// tie and 0.0 for a loss.
+#[derive(Clone, Debug)]
+pub struct Sample {
+    pub board: Board,
+    pub result: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct SelfPlayConfig {
+    pub depth: u64,
+    /// Chance of playing a random move instead of the engine's, so games do not repeat.
+    pub epsilon: f64,
+}
+
+impl Default for SelfPlayConfig {
+    fn default() -> Self {
+        SelfPlayConfig {
+            depth: 4,
+            epsilon: 0.1,
+        }
+    }
+}
+
+/// Plays a game from `start` against itself and labels every position with the result.
+pub fn play_game<E: Evaluator<Board>>(
+    start: Board,
+    evaluator: &E,
+    config: &SelfPlayConfig,
+    rng: &mut Rng,
+) -> Vec<Sample> {
+    let mut board = start;
+    let mut positions = Vec::new();
+
+    let outcome = loop {
+        if let Some(outcome) = board.outcome() {
+            break outcome;
+        }
+
+        let moves = board.moves();
+        let mv = if moves.is_empty() {
+            // Boards always have a move until the game is over
+            break Outcome::Tie;
+        } else if rng.next_f64() < config.epsilon {
+            moves[rng.below(moves.len())]
+        } else {
+            match fixed_depth(&board, evaluator, config.depth) {
+                BestMove::Best((mv, _)) => mv,
+                BestMove::GameOver(outcome) => break outcome,
+                BestMove::NoLegalMoves => break Outcome::Tie,
+            }
+        };
+
+        let next = board.play_move(mv).expect("the engine plays legal moves");
+        positions.push(board);
+        board = next;
+    };
+
+    let result = match outcome {
+        Outcome::Win(Player::X) => 1.0,
+        Outcome::Win(Player::O) => 0.0,
+        Outcome::Tie => 0.5,
+    };
+
+    positions
+        .into_iter()
+        .map(|board| Sample { board, result })
+        .collect()
+}
+
+/// Writes one sample per line, the position string followed by the result.
+pub fn write_samples(samples: &[Sample], mut writer: impl Write) -> std::io::Result<()> {
+    for sample in samples {
+        writeln!(
+            writer,
+            "{} {}",
+            sample.board.position_string(),
+            sample.result
+        )?;
+    }
+
+    Ok(())
+}
+
+pub fn read_samples(reader: impl BufRead) -> anyhow::Result<Vec<Sample>> {
+    let mut samples = Vec::new();
+
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (board, result) = line
+            .rsplit_once(' ')
+            .with_context(|| format!("Missing result in sample {line}"))?;
+
+        samples.push(Sample {
+            board: board.parse()?,
+            result: result.parse()?,
+        });
+    }
+
+    Ok(samples)
+}
@@ -0,0 +1,271 @@
+//! The precomputed values behind [`crate::eval::TableEvaluator`].
+//!
+//! By default the build script generates them as phf maps. With the `runtime-tables` feature
+//! they are instead computed once, the first time they are needed, which keeps clean builds
+//! fast at the cost of a short pause on the first evaluation.
+
+use ultimate_tic_tac_toe::{classic::Solution, Board, IndividualBoard};
+
+#[cfg(not(feature = "runtime-tables"))]
+// The build script writes every digit it has, more than an f64 keeps
+#[allow(clippy::excessive_precision)]
+mod generated {
+    use super::*;
+
+    include!(concat!(env!("OUT_DIR"), "/codegen.rs"));
+
+    pub fn local_eval(local: IndividualBoard) -> f64 {
+        *BOARD_EVALS.get(&local.key()).unwrap()
+    }
+
+    pub fn whole_board_eval(whole_board: &Board) -> f64 {
+        *WHOLE_BOARD_EVALS.get(&whole_board.key()).unwrap()
+    }
+
+    pub fn local_solutions(local: IndividualBoard) -> [Solution; 2] {
+        *LOCAL_SOLUTIONS.get(&local.key()).unwrap()
+    }
+}
+
+#[cfg(any(feature = "runtime-tables", test))]
+mod runtime {
+    use std::{cmp::Reverse, sync::LazyLock};
+
+    use ultimate_tic_tac_toe::{
+        classic::ClassicBoard,
+        evalute,
+        tables::{global_value, local_board, local_index, LOCAL_STATES},
+        Game, Player,
+    };
+
+    use super::*;
+
+    /// The solutions of every local board with each player to move, solved once and indexed by
+    /// [`local_index`].
+    static LOCAL_SOLUTIONS: LazyLock<Vec<[Solution; 2]>> = LazyLock::new(|| {
+        let mut table = vec![[None; 2]; LOCAL_STATES];
+
+        (0..LOCAL_STATES)
+            .map(|idx| {
+                [Player::X, Player::O].map(|to_play| {
+                    solve(
+                        &mut table,
+                        ClassicBoard {
+                            board: local_board(idx),
+                            to_play,
+                        },
+                    )
+                })
+            })
+            .collect()
+    });
+
+    /// [`ClassicBoard::solve`], reusing the solutions already in `table`.
+    fn solve(table: &mut [[Option<Solution>; 2]], board: ClassicBoard) -> Solution {
+        let idx = local_index(board.board).expect("squares are never claimed twice");
+        if let Some(solution) = table[idx][board.to_play as usize] {
+            return solution;
+        }
+
+        let solution = match board.outcome() {
+            Some(outcome) => Solution { outcome, plies: 0 },
+            None => {
+                board
+                    .moves()
+                    .into_iter()
+                    .map(|mv| {
+                        let child = solve(table, board.play_move(mv).unwrap());
+                        let solution = Solution {
+                            plies: child.plies + 1,
+                            ..child
+                        };
+                        (solution, mv)
+                    })
+                    .max_by_key(|&(solution, mv)| (solution.rank(board.to_play), Reverse(mv)))
+                    .expect("an unfinished board has a legal move")
+                    .0
+            }
+        };
+
+        table[idx][board.to_play as usize] = Some(solution);
+        solution
+    }
+
+    pub fn local_eval(local: IndividualBoard) -> f64 {
+        (evalute(local, Player::X) + evalute(local, Player::O)) / 2.0
+    }
+
+    pub fn whole_board_eval(whole_board: &Board) -> f64 {
+        (global_value(whole_board, Player::X) + global_value(whole_board, Player::O)) / 2.0
+    }
+
+    pub fn local_solutions(local: IndividualBoard) -> [Solution; 2] {
+        LOCAL_SOLUTIONS[local_index(local).expect("squares are never claimed twice")]
+    }
+}
+
+#[cfg(not(feature = "runtime-tables"))]
+pub use generated::*;
+#[cfg(feature = "runtime-tables")]
+pub use runtime::*;
+
+#[cfg(test)]
+mod tests {
+    use ultimate_tic_tac_toe::{
+        classic::ClassicBoard,
+        tables::{global_board, local_board, GLOBAL_STATES, LOCAL_STATES},
+        Game, Outcome, Player,
+    };
+
+    use super::*;
+
+    /// The random playout value of a local board, walked out square by square the way it was
+    /// computed before there were tables.
+    fn playout_value(board: IndividualBoard, player: Player) -> f64 {
+        if board.is_tie() {
+            return 0.0;
+        } else if let Some(winner) = board.has_won() {
+            return if winner == Player::X { 1.0 } else { -1.0 };
+        }
+
+        (0..9)
+            .filter(|idx| ((board.0 | board.1) >> idx) & 1 == 0)
+            .map(|idx| {
+                let mut child = board;
+                match player {
+                    Player::X => child.0 |= 1 << idx,
+                    Player::O => child.1 |= 1 << idx,
+                }
+                playout_value(child, player.invert())
+            })
+            .sum::<f64>()
+            / 9.0
+    }
+
+    /// Like [`playout_value`] for the board of decided local boards.
+    fn global_playout_value(board: Board, player: Player) -> f64 {
+        if board.is_tie() {
+            return 0.0;
+        } else if let Some(winner) = board.has_won() {
+            return if winner == Player::X { 1.0 } else { -1.0 };
+        }
+
+        (0..9)
+            .filter(|idx| ((board.global_full | board.global_o | board.global_x) >> idx) & 1 == 0)
+            .map(|idx| {
+                let mut child = board.clone();
+                match player {
+                    Player::X => child.global_x |= 1 << idx,
+                    Player::O => child.global_o |= 1 << idx,
+                }
+                global_playout_value(child, player.invert())
+            })
+            .sum::<f64>()
+            / 9.0
+    }
+
+    /// The winner under perfect play and how many plies the win takes, by plain minimax.
+    fn perfect_play(board: ClassicBoard) -> (Outcome, u8) {
+        if let Some(outcome) = board.outcome() {
+            return (outcome, 0);
+        }
+
+        let rank = |(outcome, plies): (Outcome, u8)| match outcome {
+            Outcome::Win(winner) if winner == board.to_play => 100 - plies as i16,
+            Outcome::Win(_) => -100 + plies as i16,
+            Outcome::Tie => 0,
+        };
+
+        board
+            .moves()
+            .into_iter()
+            .map(|mv| {
+                let (outcome, plies) = perfect_play(board.play_move(mv).unwrap());
+                (outcome, plies + 1)
+            })
+            .max_by_key(|&result| rank(result))
+            .unwrap()
+    }
+
+    #[test]
+    fn local_tables_match_reference() {
+        for idx in (0..LOCAL_STATES).step_by(7) {
+            let local = local_board(idx);
+            let expected =
+                (playout_value(local, Player::X) + playout_value(local, Player::O)) / 2.0;
+
+            assert!(
+                (local_eval(local) - expected).abs() < 1e-12,
+                "local board {idx}"
+            );
+
+            for (solution, to_play) in local_solutions(local)
+                .into_iter()
+                .zip([Player::X, Player::O])
+            {
+                let (outcome, plies) = perfect_play(ClassicBoard {
+                    board: local,
+                    to_play,
+                });
+
+                assert_eq!(solution.outcome, outcome, "local board {idx}");
+                // Ties can take any number of plies depending on the line picked
+                if outcome != Outcome::Tie {
+                    assert_eq!(solution.plies, plies, "local board {idx}");
+                }
+            }
+        }
+    }
+
+    #[cfg(not(feature = "runtime-tables"))]
+    #[test]
+    fn runtime_tables_match_generated() {
+        for idx in 0..LOCAL_STATES {
+            let local = local_board(idx);
+
+            assert!((runtime::local_eval(local) - local_eval(local)).abs() < 1e-12);
+            assert_eq!(
+                runtime::local_solutions(local),
+                local_solutions(local),
+                "local board {idx}"
+            );
+        }
+
+        for idx in (0..GLOBAL_STATES).step_by(13) {
+            let (global_x, global_o, global_full) = global_board(idx);
+            let whole_board = Board {
+                global_x,
+                global_o,
+                global_full,
+                ..Board::default()
+            };
+
+            assert!(
+                (runtime::whole_board_eval(&whole_board) - whole_board_eval(&whole_board)).abs()
+                    < 1e-12,
+                "global board {idx}"
+            );
+        }
+    }
+
+    #[test]
+    fn global_table_matches_reference() {
+        for idx in (0..GLOBAL_STATES).step_by(97) {
+            let (global_x, global_o, global_full) = global_board(idx);
+            let whole_board = Board {
+                global_x,
+                global_o,
+                global_full,
+                ..Board::default()
+            };
+            let expected = (global_playout_value(whole_board.clone(), Player::X)
+                + global_playout_value(whole_board.clone(), Player::O))
+                / 2.0;
+
+            assert!(
+                (whole_board_eval(&whole_board) - expected).abs() < 1e-12,
+                "global board {idx}"
+            );
+        }
+    }
+}
@@ -0,0 +1,203 @@
+//! The transposition table: results of earlier searches by position hash, so a position reached
+//! again through another move order or in a later iteration is not searched from scratch.
+//!
+//! Replacement works per bucket of two entries. The deep entry keeps the deepest result stored
+//! since the last [`TranspositionTable::new_search`], and is only replaced by a result at least
+//! as deep until the next search starts. Everything else goes into the recent entry, which is
+//! always replaced.
+
+use std::sync::{
+    atomic::{self, AtomicU8},
+    Mutex,
+};
+
+use ultimate_tic_tac_toe::Score;
+
+/// What a stored score says about the true score of a position.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bound {
+    Exact,
+    /// The search failed high, the true score is at least this.
+    Lower,
+    /// The search failed low, the true score is at most this.
+    Upper,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Entry<M> {
+    /// The full position hash, to tell apart positions that share a slot.
+    pub key: u64,
+    pub depth: u64,
+    /// The score counted from this position rather than from the root, see [`to_table`].
+    pub score: Score,
+    pub bound: Bound,
+    pub best_move: Option<M>,
+}
+
+/// A fixed size, thread safe cache of search results indexed by position hash.
+///
+/// Each hash maps to a bucket of two entries. One keeps the deepest result of the current
+/// search, so the results near the root that are the most work to redo survive, and the other
+/// takes whatever else comes along.
+pub struct TranspositionTable<M> {
+    buckets: Vec<Mutex<Bucket<M>>>,
+    generation: AtomicU8,
+}
+
+#[derive(Clone, Copy)]
+struct Bucket<M> {
+    /// The deepest entry and the search that stored it.
+    deep: Option<(Entry<M>, u8)>,
+    recent: Option<Entry<M>>,
+}
+
+impl<M: Copy> TranspositionTable<M> {
+    /// A table holding up to `entries` results, rounded down to a power of two.
+    pub fn with_entries(entries: usize) -> Self {
+        let buckets = (entries / 2).max(1);
+        let buckets = if buckets.is_power_of_two() {
+            buckets
+        } else {
+            buckets.next_power_of_two() / 2
+        };
+
+        TranspositionTable {
+            buckets: (0..buckets)
+                .map(|_| {
+                    Mutex::new(Bucket {
+                        deep: None,
+                        recent: None,
+                    })
+                })
+                .collect(),
+            generation: AtomicU8::new(0),
+        }
+    }
+
+    /// A table taking up about `megabytes` of memory.
+    pub fn with_megabytes(megabytes: usize) -> Self {
+        Self::with_entries(megabytes * 1024 * 1024 / std::mem::size_of::<Mutex<Bucket<M>>>() * 2)
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buckets.len() * 2
+    }
+
+    /// Marks the start of a new search, after which deep entries of earlier searches may be
+    /// replaced by shallower ones.
+    pub fn new_search(&self) {
+        self.generation.fetch_add(1, atomic::Ordering::Relaxed);
+    }
+
+    fn bucket(&self, key: u64) -> &Mutex<Bucket<M>> {
+        &self.buckets[key as usize & (self.buckets.len() - 1)]
+    }
+
+    pub fn probe(&self, key: u64) -> Option<Entry<M>> {
+        let bucket = self.bucket(key).lock().unwrap();
+
+        bucket
+            .deep
+            .map(|(entry, _)| entry)
+            .filter(|entry| entry.key == key)
+            .or(bucket.recent.filter(|entry| entry.key == key))
+    }
+
+    pub fn store(&self, entry: Entry<M>) {
+        let generation = self.generation.load(atomic::Ordering::Relaxed);
+        let mut bucket = self.bucket(entry.key).lock().unwrap();
+
+        let replaces_deep = bucket.deep.is_none_or(|(old, old_generation)| {
+            old_generation != generation || old.depth <= entry.depth
+        });
+
+        if replaces_deep {
+            bucket.deep = Some((entry, generation));
+        } else {
+            bucket.recent = Some(entry);
+        }
+    }
+
+    pub fn clear(&self) {
+        for bucket in &self.buckets {
+            *bucket.lock().unwrap() = Bucket {
+                deep: None,
+                recent: None,
+            };
+        }
+    }
+}
+
+/// Converts a score counted from the root into one counted from a position `ply` plies down,
+/// so a stored win stays correct when the position is reached at a different ply.
+pub fn to_table(score: Score, ply: u32) -> Score {
+    match score.win() {
+        Some((player, plies)) => Score::win_in(player, plies.saturating_sub(ply)),
+        None => score,
+    }
+}
+
+/// The inverse of [`to_table`].
+pub fn from_table(score: Score, ply: u32) -> Score {
+    match score.win() {
+        Some((player, plies)) => Score::win_in(player, plies + ply),
+        None => score,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ultimate_tic_tac_toe::Player;
+
+    use super::*;
+
+    fn entry(key: u64, depth: u64) -> Entry<u8> {
+        Entry {
+            key,
+            depth,
+            score: Score::DRAW,
+            bound: Bound::Exact,
+            best_move: None,
+        }
+    }
+
+    #[test]
+    fn deep_entries_survive_shallower_ones_until_the_next_search() {
+        let table = TranspositionTable::with_entries(2);
+        table.new_search();
+
+        // With one bucket every key shares it
+        table.store(entry(1, 5));
+        table.store(entry(2, 1));
+        assert_eq!(table.probe(1).map(|entry| entry.depth), Some(5));
+        assert_eq!(table.probe(2).map(|entry| entry.depth), Some(1));
+
+        table.store(entry(3, 2));
+        assert_eq!(table.probe(1).map(|entry| entry.depth), Some(5));
+        assert!(table.probe(2).is_none());
+
+        table.new_search();
+        table.store(entry(4, 1));
+        assert!(table.probe(1).is_none());
+        assert_eq!(table.probe(4).map(|entry| entry.depth), Some(1));
+    }
+
+    #[test]
+    fn wins_keep_their_distance_through_the_table() {
+        let win = Score::win_in(Player::X, 7);
+
+        for ply in [0, 3, 7] {
+            assert_eq!(from_table(to_table(win, ply), ply), win);
+        }
+
+        // Stored 3 plies down, where the win is 4 plies away, and found again 5 plies down
+        assert_eq!(to_table(win, 3), Score::win_in(Player::X, 4));
+        assert_eq!(from_table(to_table(win, 3), 5), Score::win_in(Player::X, 9));
+
+        let loss = Score::win_in(Player::O, 6);
+        assert_eq!(from_table(to_table(loss, 2), 2), loss);
+
+        let eval = Score::from_eval(0.3);
+        assert_eq!(from_table(to_table(eval, 4), 9), eval);
+    }
+}
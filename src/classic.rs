@@ -0,0 +1,183 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    game::{mix, Game, Outcome},
+    IndividualBoard, Player,
+};
+
+/// A plain game of 3x3 tic-tac-toe played on a single [`IndividualBoard`].
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize, Copy)]
+pub struct ClassicBoard {
+    pub board: IndividualBoard,
+    pub to_play: Player,
+}
+
+impl Default for ClassicBoard {
+    fn default() -> Self {
+        ClassicBoard {
+            board: IndividualBoard(0, 0),
+            to_play: Player::X,
+        }
+    }
+}
+
+/// The game theoretic value of a position under perfect play.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize, Copy)]
+pub struct Solution {
+    pub outcome: Outcome,
+    /// Plies until the game ends when the winner hurries and the loser stalls.
+    pub plies: u8,
+}
+
+impl Solution {
+    /// Orders solutions from the point of view of `player`, higher is better.
+    pub fn rank(self, player: Player) -> i16 {
+        match self.outcome {
+            Outcome::Win(winner) if winner == player => 100 - self.plies as i16,
+            Outcome::Win(_) => -100 + self.plies as i16,
+            Outcome::Tie => 0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ClassicMoveResult {
+    pub local: Option<usize>,
+    pub solution: Solution,
+}
+
+impl ClassicBoard {
+    pub fn play(&self, idx: usize) -> Option<Self> {
+        self.play_move(idx)
+    }
+
+    pub fn has_won(&self) -> Option<Player> {
+        self.board.has_won()
+    }
+
+    pub fn is_tie(&self) -> bool {
+        self.has_won().is_none() && self.board.is_tie()
+    }
+
+    /// Solves the position by searching every continuation.
+    pub fn solve(&self) -> Solution {
+        self.analyze().solution
+    }
+
+    /// The move perfect play picks here, preferring faster wins and slower losses.
+    pub fn best_move(&self) -> Option<usize> {
+        self.analyze().local
+    }
+
+    /// The perfect play move together with the value of the position.
+    pub fn analyze(&self) -> ClassicMoveResult {
+        if let Some(outcome) = self.outcome() {
+            return ClassicMoveResult {
+                local: None,
+                solution: Solution { outcome, plies: 0 },
+            };
+        }
+
+        self.moves()
+            .into_iter()
+            .filter_map(|idx| Some((idx, self.play(idx)?.solve())))
+            .map(|(idx, solution)| ClassicMoveResult {
+                local: Some(idx),
+                solution: Solution {
+                    outcome: solution.outcome,
+                    plies: solution.plies + 1,
+                },
+            })
+            .max_by_key(|result| {
+                (
+                    result.solution.rank(self.to_play),
+                    std::cmp::Reverse(result.local),
+                )
+            })
+            .expect("an unfinished board has a legal move")
+    }
+}
+
+impl Game for ClassicBoard {
+    type Move = usize;
+
+    fn moves(&self) -> Vec<usize> {
+        if self.board.get_state().is_some() {
+            return Vec::new();
+        }
+
+        (0..9)
+            .filter(|idx| ((self.board.0 | self.board.1) >> idx) & 1 == 0)
+            .collect()
+    }
+
+    fn play_move(&self, mv: usize) -> Option<Self> {
+        if mv >= 9 || self.board.get_state().is_some() {
+            return None;
+        }
+
+        if ((self.board.0 | self.board.1) >> mv) & 1 == 1 {
+            return None;
+        }
+
+        let mut new_self = *self;
+
+        if self.to_play == Player::X {
+            new_self.board.0 |= 1 << mv;
+        } else {
+            new_self.board.1 |= 1 << mv;
+        }
+
+        new_self.to_play = self.to_play.invert();
+
+        Some(new_self)
+    }
+
+    fn outcome(&self) -> Option<Outcome> {
+        self.board.get_state()
+    }
+
+    fn side_to_move(&self) -> Player {
+        self.to_play
+    }
+
+    fn hash_key(&self) -> u64 {
+        mix(self.board.key() as u64, self.to_play as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_board_is_a_tie_in_nine_plies() {
+        assert_eq!(
+            ClassicBoard::default().solve(),
+            Solution {
+                outcome: Outcome::Tie,
+                plies: 9,
+            }
+        );
+    }
+
+    #[test]
+    fn fork_is_a_win_in_three_plies() {
+        // X holds a corner and the centre, so either square below the corner makes two threats
+        let board = ClassicBoard {
+            board: IndividualBoard(0b000_010_001, 0b100_000_010),
+            to_play: Player::X,
+        };
+
+        let result = board.analyze();
+
+        assert!(matches!(result.local, Some(3 | 6)));
+        assert_eq!(
+            result.solution,
+            Solution {
+                outcome: Outcome::Win(Player::X),
+                plies: 3,
+            }
+        );
+    }
+}
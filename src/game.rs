@@ -0,0 +1,40 @@
+use std::{fmt::Debug, hash::Hash};
+
+use crate::{LocalBoardState, Player};
+
+/// The result of a finished game, using the same shape as a decided local board.
+pub type Outcome = LocalBoardState;
+
+/// A two player, perfect information game that the search engines can play.
+///
+/// X is always the maximizing side and O the minimizing side.
+pub trait Game: Clone + Send + Sync {
+    type Move: Copy + Eq + Hash + Debug + Send + Sync;
+
+    /// Every legal move in this position, empty once the game is over.
+    fn moves(&self) -> Vec<Self::Move>;
+
+    /// Plays a move, returning `None` if it is not legal.
+    fn play_move(&self, mv: Self::Move) -> Option<Self>;
+
+    fn outcome(&self) -> Option<Outcome>;
+
+    fn side_to_move(&self) -> Player;
+
+    /// A hash of the full position, including the side to move.
+    fn hash_key(&self) -> u64;
+
+    /// A cheap guess at how good a move is for the side to move, higher first. Searches try
+    /// promising moves first so they can prune the rest sooner.
+    fn move_priority(&self, _mv: Self::Move) -> i32 {
+        0
+    }
+}
+
+/// Folds `value` into `hash` using the splitmix64 finalizer.
+pub(crate) fn mix(hash: u64, value: u64) -> u64 {
+    let mut z = hash ^ value.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,333 @@
+use anyhow::{bail, ensure};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    game::{mix, Game, Outcome},
+    LocalBoardState, Player,
+};
+
+/// An ultimate board made of `size` by `size` local boards, each `size` by `size` cells, where
+/// `win_length` in a row wins a local board and `win_length` won local boards in a row win the game.
+///
+/// Boards and cells are indexed row by row, the same way [`crate::Board`] indexes them.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "UncheckedGeneralBoard")]
+pub struct GeneralBoard {
+    pub size: usize,
+    pub win_length: usize,
+    pub cells: Vec<Option<Player>>,
+    pub globals: Vec<Option<LocalBoardState>>,
+    pub to_play: Player,
+    pub global_idx: Option<usize>,
+}
+
+/// A [`GeneralBoard`] as it arrives, before the lengths are checked against the size.
+#[derive(Deserialize)]
+struct UncheckedGeneralBoard {
+    size: usize,
+    win_length: usize,
+    cells: Vec<Option<Player>>,
+    globals: Vec<Option<LocalBoardState>>,
+    to_play: Player,
+    global_idx: Option<usize>,
+}
+
+impl TryFrom<UncheckedGeneralBoard> for GeneralBoard {
+    type Error = anyhow::Error;
+
+    fn try_from(board: UncheckedGeneralBoard) -> Result<Self, Self::Error> {
+        let boards = board.size * board.size;
+
+        ensure!(
+            board.win_length > 0 && board.win_length <= board.size,
+            "Win length {} must be between 1 and the board size {}",
+            board.win_length,
+            board.size
+        );
+        ensure!(
+            board.cells.len() == boards * boards,
+            "Expected {} cells, got {}",
+            boards * boards,
+            board.cells.len()
+        );
+        ensure!(
+            board.globals.len() == boards,
+            "Expected {} local boards, got {}",
+            boards,
+            board.globals.len()
+        );
+        if let Some(idx) = board.global_idx.filter(|&idx| idx >= boards) {
+            bail!("Forced into board {} of {}", idx, boards);
+        }
+
+        Ok(GeneralBoard {
+            size: board.size,
+            win_length: board.win_length,
+            cells: board.cells,
+            globals: board.globals,
+            to_play: board.to_play,
+            global_idx: board.global_idx,
+        })
+    }
+}
+
+const DIRECTIONS: [(isize, isize); 4] = [(0, 1), (1, 0), (1, 1), (1, -1)];
+
+/// Whether the square at `idx` of a `size` by `size` grid is part of `win_length` in a row of
+/// squares for which `owned` returns true.
+fn has_line_through(
+    size: usize,
+    win_length: usize,
+    idx: usize,
+    owned: impl Fn(usize) -> bool,
+) -> bool {
+    let (row, col) = ((idx / size) as isize, (idx % size) as isize);
+    let in_bounds = |row: isize, col: isize| {
+        row >= 0 && col >= 0 && (row as usize) < size && (col as usize) < size
+    };
+
+    DIRECTIONS.into_iter().any(|(d_row, d_col)| {
+        let count_from = |sign: isize| {
+            (1..win_length as isize)
+                .take_while(|step| {
+                    let (row, col) = (row + d_row * step * sign, col + d_col * step * sign);
+                    in_bounds(row, col) && owned(row as usize * size + col as usize)
+                })
+                .count()
+        };
+
+        1 + count_from(1) + count_from(-1) >= win_length
+    })
+}
+
+impl GeneralBoard {
+    /// An empty board, panics if `win_length` is zero or longer than a side.
+    pub fn new(size: usize, win_length: usize) -> Self {
+        assert!(
+            win_length > 0 && win_length <= size,
+            "win length must be between 1 and the board size"
+        );
+
+        let boards = size * size;
+
+        GeneralBoard {
+            size,
+            win_length,
+            cells: vec![None; boards * boards],
+            globals: vec![None; boards],
+            to_play: Player::X,
+            global_idx: None,
+        }
+    }
+
+    /// Number of local boards, which is also the number of cells in each local board.
+    pub fn boards(&self) -> usize {
+        self.size * self.size
+    }
+
+    pub fn get_local(&self, idx: usize) -> &[Option<Player>] {
+        let boards = self.boards();
+        &self.cells[idx * boards..(idx + 1) * boards]
+    }
+
+    pub fn get_state(&self, idx: usize) -> Option<LocalBoardState> {
+        self.globals[idx]
+    }
+
+    pub fn has_won(&self) -> Option<Player> {
+        [Player::X, Player::O].into_iter().find(|&player| {
+            (0..self.boards()).any(|idx| {
+                self.globals[idx] == Some(LocalBoardState::Win(player))
+                    && has_line_through(self.size, self.win_length, idx, |idx| {
+                        self.globals[idx] == Some(LocalBoardState::Win(player))
+                    })
+            })
+        })
+    }
+
+    pub fn is_tie(&self) -> bool {
+        self.globals.iter().all(Option::is_some) && self.has_won().is_none()
+    }
+
+    pub fn play(&self, global: usize, local: usize) -> Option<Self> {
+        let boards = self.boards();
+
+        if global >= boards || local >= boards || self.outcome().is_some() {
+            return None;
+        }
+
+        if matches!(self.global_idx, Some(allowed_global) if allowed_global != global) {
+            return None;
+        }
+
+        if self.globals[global].is_some() || self.get_local(global)[local].is_some() {
+            return None;
+        }
+
+        let mut new_self = self.to_owned();
+        new_self.cells[global * boards + local] = Some(self.to_play);
+
+        let cells = new_self.get_local(global);
+        if has_line_through(self.size, self.win_length, local, |idx| {
+            cells[idx] == Some(self.to_play)
+        }) {
+            new_self.globals[global] = Some(LocalBoardState::Win(self.to_play));
+        } else if cells.iter().all(Option::is_some) {
+            new_self.globals[global] = Some(LocalBoardState::Tie);
+        }
+
+        new_self.to_play = self.to_play.invert();
+        new_self.global_idx = if new_self.globals[local].is_some() {
+            None
+        } else {
+            Some(local)
+        };
+
+        Some(new_self)
+    }
+}
+
+impl Game for GeneralBoard {
+    type Move = (usize, usize);
+
+    fn moves(&self) -> Vec<(usize, usize)> {
+        if self.outcome().is_some() {
+            return Vec::new();
+        }
+
+        let boards = self.boards();
+        let globals = match self.global_idx {
+            Some(idx) => idx..idx + 1,
+            None => 0..boards,
+        };
+
+        globals
+            .filter(|&global| self.globals[global].is_none())
+            .flat_map(|global| {
+                (0..boards)
+                    .filter(move |&local| self.get_local(global)[local].is_none())
+                    .map(move |local| (global, local))
+            })
+            .collect()
+    }
+
+    fn play_move(&self, (global, local): (usize, usize)) -> Option<Self> {
+        self.play(global, local)
+    }
+
+    fn outcome(&self) -> Option<Outcome> {
+        if let Some(winner) = self.has_won() {
+            Some(Outcome::Win(winner))
+        } else if self.is_tie() {
+            Some(Outcome::Tie)
+        } else {
+            None
+        }
+    }
+
+    fn side_to_move(&self) -> Player {
+        self.to_play
+    }
+
+    fn hash_key(&self) -> u64 {
+        let hash = self
+            .cells
+            .chunks(32)
+            .map(|chunk| {
+                chunk.iter().fold(0, |acc, cell| {
+                    acc << 2
+                        | match cell {
+                            None => 0,
+                            Some(Player::X) => 1,
+                            Some(Player::O) => 2,
+                        }
+                })
+            })
+            .fold(mix(self.size as u64, self.win_length as u64), mix);
+
+        mix(
+            hash,
+            (self.to_play as u64) << 32 | self.global_idx.map_or(u32::MAX as u64, |idx| idx as u64),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Board;
+
+    #[test]
+    fn three_by_three_plays_like_board() {
+        // A fixed LCG keeps the games the same from run to run
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut next = |bound: usize| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) as usize % bound
+        };
+
+        for _ in 0..200 {
+            let mut board = Board::default();
+            let mut general = GeneralBoard::new(3, 3);
+
+            loop {
+                let moves = board.moves();
+                assert_eq!(moves, general.moves());
+                assert_eq!(board.outcome(), general.outcome());
+
+                if moves.is_empty() {
+                    break;
+                }
+
+                let mv = moves[next(moves.len())];
+                board = board.play_move(mv).unwrap();
+                general = general.play_move(mv).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn four_by_four_needs_three_in_a_row() {
+        let mut board = GeneralBoard::new(4, 3);
+
+        // Two X cells on a diagonal of local board 5 that does not start in a corner
+        board.cells[5 * 16 + 1] = Some(Player::X);
+        board.cells[5 * 16 + 6] = Some(Player::X);
+        board.global_idx = Some(5);
+
+        let played = board.play(5, 11).unwrap();
+        assert_eq!(played.get_state(5), Some(LocalBoardState::Win(Player::X)));
+        assert_eq!(played.outcome(), None);
+
+        board.globals[0] = Some(LocalBoardState::Win(Player::X));
+        board.globals[1] = Some(LocalBoardState::Win(Player::X));
+        board.globals[3] = Some(LocalBoardState::Win(Player::X));
+        assert_eq!(board.outcome(), None);
+
+        board.globals[2] = Some(LocalBoardState::Win(Player::X));
+        assert_eq!(board.outcome(), Some(Outcome::Win(Player::X)));
+    }
+
+    #[test]
+    fn deserializing_checks_the_lengths() {
+        let board = GeneralBoard::new(3, 3);
+        let json = serde_json::to_string(&board).unwrap();
+        assert_eq!(serde_json::from_str::<GeneralBoard>(&json).unwrap(), board);
+
+        let short = GeneralBoard {
+            cells: vec![None; 80],
+            ..board.clone()
+        };
+        let json = serde_json::to_string(&short).unwrap();
+        assert!(serde_json::from_str::<GeneralBoard>(&json).is_err());
+
+        let wrong_globals = GeneralBoard {
+            globals: vec![None; 4],
+            ..board
+        };
+        let json = serde_json::to_string(&wrong_globals).unwrap();
+        assert!(serde_json::from_str::<GeneralBoard>(&json).is_err());
+    }
+}
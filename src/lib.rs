@@ -1,19 +1,34 @@
-use std::{
-    cmp::Ordering,
-    collections::HashMap,
-    fmt::Display,
-    sync::atomic::{self, AtomicU64},
-};
-
-use dashmap::DashMap;
-use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use std::fmt::Display;
+
 use serde::{Deserialize, Serialize};
 
+pub mod classic;
+pub mod game;
+pub mod general;
+pub mod notation;
+pub mod pie;
+pub mod score;
+pub mod tables;
+
+pub use game::{Game, Outcome};
+pub use score::Score;
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct MiniMaxResult {
     pub global: usize,
     pub local: usize,
-    pub eval: f64,
+    pub eval: Score,
+    /// The line of play the engine expects as `(global, local)` moves, starting with its own.
+    pub pv: Vec<(usize, usize)>,
+}
+
+/// How much each part of a board adds to its evaluation, positive when X is ahead.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EvaluationBreakdown {
+    /// The contribution of each local board, indexed like the boards themselves.
+    pub locals: [f64; 9],
+    pub global: f64,
+    pub total: f64,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
@@ -84,6 +99,18 @@ const DOWN_RIGHT: u16 = DOWN_MIDDLE >> 1;
 const DIAG1: u16 = 0b100010001;
 const DIAG2: u16 = 0b001010100;
 
+/// Every three in a row on a 3x3 board, as masks of square indices.
+pub const WIN_LINES: [u16; 8] = [
+    ACROSS_TOP,
+    ACROSS_MIDDLE,
+    ACROSS_BOTTOM,
+    DOWN_LEFT,
+    DOWN_MIDDLE,
+    DOWN_RIGHT,
+    DIAG1,
+    DIAG2,
+];
+
 fn has_won_raw(board: u16) -> bool {
     board & ACROSS_TOP == ACROSS_TOP
         || board & ACROSS_MIDDLE == ACROSS_MIDDLE
@@ -214,13 +241,87 @@ impl Board {
             .sum::<f64>()
             / 18.0;
 
-        let sum1 = evaluate_whole(self.to_owned(), Player::X);
-        let sum2 = evaluate_whole(self.to_owned(), Player::O);
+        let sum1 = tables::global_value(self, Player::X);
+        let sum2 = tables::global_value(self, Player::O);
 
         sum + (sum1 + sum2) / 2.0
     }
 }
 
+impl Game for Board {
+    type Move = (usize, usize);
+
+    fn moves(&self) -> Vec<(usize, usize)> {
+        if self.outcome().is_some() {
+            return Vec::new();
+        }
+
+        let range = if let Some(idx) = self.global_idx {
+            idx * 9..idx * 9 + 9
+        } else {
+            0..81
+        };
+
+        let decided = self.global_x | self.global_o | self.global_full;
+
+        range
+            .filter(|idx| ((self.locals_x | self.locals_o) >> idx) & 1 == 0)
+            .filter(|idx| (decided >> (idx / 9)) & 1 == 0)
+            .map(|idx| (idx / 9, idx % 9))
+            .collect()
+    }
+
+    fn play_move(&self, (global, local): (usize, usize)) -> Option<Self> {
+        self.play(global, local)
+    }
+
+    fn outcome(&self) -> Option<Outcome> {
+        if let Some(winner) = self.has_won() {
+            Some(Outcome::Win(winner))
+        } else if self.is_tie() {
+            Some(Outcome::Tie)
+        } else {
+            None
+        }
+    }
+
+    fn side_to_move(&self) -> Player {
+        self.to_play
+    }
+
+    fn hash_key(&self) -> u64 {
+        let hash = game::mix(0, self.locals_x as u64);
+        let hash = game::mix(hash, (self.locals_x >> 64) as u64);
+        let hash = game::mix(hash, self.locals_o as u64);
+        let hash = game::mix(hash, (self.locals_o >> 64) as u64);
+
+        game::mix(
+            hash,
+            (self.to_play as u64) << 8 | self.global_idx.map_or(9, |idx| idx as u64),
+        )
+    }
+
+    /// Prefers moves that win a local board, then moves that send the opponent to a decided
+    /// board or to a board that is bad for them.
+    fn move_priority(&self, (global, local): (usize, usize)) -> i32 {
+        let Some(next) = self.play(global, local) else {
+            return i32::MIN;
+        };
+
+        let sign = match self.to_play {
+            Player::X => 1.0,
+            Player::O => -1.0,
+        };
+        let won_board = next.global_x | next.global_o != self.global_x | self.global_o;
+        let destination = match next.global_idx {
+            Some(idx) => sign * tables::local_value(next.get_local(idx), next.to_play),
+            None => 1.0,
+        };
+
+        won_board as i32 * 2000 + (destination * 1000.0) as i32
+    }
+}
+
 fn get_player_at_idx(board: IndividualBoard, idx: u16) -> Option<Player> {
     if (board.0 >> idx) & 1 == 1 {
         Some(Player::X)
@@ -271,56 +372,13 @@ impl Display for Board {
     }
 }
 
+/// The random playout value of a local board when `player` moves next: 1.0 if X has won, -1.0 if
+/// O has won, otherwise a ninth of the value after each empty square is played.
 pub fn evalute(board: IndividualBoard, player: Player) -> f64 {
-    if board.is_tie() {
-        return 0.0;
-    } else if let Some(winner) = board.has_won() {
-        match winner {
-            Player::X => return 1.0,
-            Player::O => return -1.0,
-        }
-    }
-
-    let res = (0..9)
-        .filter(|idx| ((board.0 | board.1) >> idx) & 1 == 0)
-        .map(|idx| {
-            let mut new_board = board.clone();
-            if player == Player::X {
-                new_board.0 |= 1 << idx;
-            } else {
-                new_board.1 |= 1 << idx;
-            }
-            evalute(new_board, player.invert())
-        })
-        .sum::<f64>()
-        / 9.0;
-
-    res
+    tables::local_value(board, player)
 }
 
+/// The same value as [`evalute`] for the board of decided local boards, ignoring the squares.
 pub fn evaluate_whole(board: Board, player: Player) -> f64 {
-    if board.is_tie() {
-        return 0.0;
-    } else if let Some(winner) = board.has_won() {
-        match winner {
-            Player::X => return 1.0,
-            Player::O => return -1.0,
-        }
-    }
-
-    let res = (0..9)
-        .filter(|idx| ((board.global_full | board.global_o | board.global_x) >> idx) & 1 == 0)
-        .map(|idx| {
-            let mut new_board = board.clone();
-            if player == Player::X {
-                new_board.global_x |= 1 << idx;
-            } else {
-                new_board.global_o |= 1 << idx;
-            }
-            evaluate_whole(new_board, player.invert())
-        })
-        .sum::<f64>()
-        / 9.0;
-
-    res
+    tables::global_value(&board, player)
 }
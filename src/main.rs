@@ -1,6 +1,3 @@
-use dashmap::DashMap;
-use ultimate_tic_tac_toe::{evalute, Board, IndividualBoard, Player};
-
 fn main() {
     // let mut board = Board::default();
     // board = board.play(4, 0).unwrap();
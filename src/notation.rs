@@ -0,0 +1,189 @@
+//! A compact, single line notation for [`Board`] positions.
+//!
+//! The nine local boards are written row by row as `X`, `O` or `.` and separated by `/`,
+//! followed by the side to move and the board the next move is forced into, or `-` for a free
+//! move. The empty board is
+//! `........./........./........./........./........./........./........./........./......... X -`.
+
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail};
+use nom::{
+    branch::alt,
+    character::complete::{char, one_of, space1},
+    combinator::{all_consuming, map, value},
+    multi::count,
+    sequence::{preceded, tuple},
+    IResult,
+};
+
+use crate::{Board, LocalBoardState, Player};
+
+fn cell(input: &str) -> IResult<&str, Option<Player>> {
+    alt((
+        value(Some(Player::X), char('X')),
+        value(Some(Player::O), char('O')),
+        value(None, char('.')),
+    ))(input)
+}
+
+fn local(input: &str) -> IResult<&str, Vec<Option<Player>>> {
+    count(cell, 9)(input)
+}
+
+fn player(input: &str) -> IResult<&str, Player> {
+    alt((value(Player::X, char('X')), value(Player::O, char('O'))))(input)
+}
+
+fn global_idx(input: &str) -> IResult<&str, Option<usize>> {
+    alt((
+        value(None, char('-')),
+        map(one_of("012345678"), |digit| {
+            digit.to_digit(10).map(|digit| digit as usize)
+        }),
+    ))(input)
+}
+
+type Position = (
+    Vec<Option<Player>>,
+    Vec<Vec<Option<Player>>>,
+    Player,
+    Option<usize>,
+);
+
+fn position(input: &str) -> IResult<&str, Position> {
+    tuple((
+        local,
+        count(preceded(char('/'), local), 8),
+        preceded(space1, player),
+        preceded(space1, global_idx),
+    ))(input)
+}
+
+impl Board {
+    /// Writes the position in the notation described in [`crate::notation`].
+    pub fn position_string(&self) -> String {
+        let locals = (0..9)
+            .map(|idx| {
+                self.get_local(idx)
+                    .squares()
+                    .map(|square| match square {
+                        Some(Player::X) => 'X',
+                        Some(Player::O) => 'O',
+                        None => '.',
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+
+        let global_idx = match self.global_idx {
+            Some(idx) => idx.to_string(),
+            None => "-".to_string(),
+        };
+
+        format!("{} {} {}", locals, self.to_play(), global_idx)
+    }
+}
+
+impl FromStr for Board {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (_, (first, rest, to_play, global_idx)) = all_consuming(position)(s.trim())
+            .map_err(|err| anyhow!("Invalid position string: {}", err))?;
+
+        let mut board = Board {
+            to_play,
+            global_idx,
+            ..Board::default()
+        };
+
+        for (global, cells) in std::iter::once(first).chain(rest).enumerate() {
+            for (local, cell) in cells.into_iter().enumerate() {
+                match cell {
+                    Some(Player::X) => board.locals_x |= 1 << (global * 9 + local),
+                    Some(Player::O) => board.locals_o |= 1 << (global * 9 + local),
+                    None => {}
+                }
+            }
+
+            match board.get_local(global).get_state() {
+                Some(LocalBoardState::Tie) => board.global_full |= 1 << global,
+                Some(LocalBoardState::Win(Player::O)) => board.global_o |= 1 << global,
+                Some(LocalBoardState::Win(Player::X)) => board.global_x |= 1 << global,
+                None => {}
+            }
+        }
+
+        if let Some(idx) = global_idx {
+            if ((board.global_x | board.global_o | board.global_full) >> idx) & 1 == 1 {
+                bail!("Forced into board {} which is already decided", idx);
+            }
+        }
+
+        Ok(board)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Game;
+
+    const EMPTY: &str =
+        "........./........./........./........./........./........./........./........./......... X -";
+
+    #[test]
+    fn positions_round_trip() {
+        // A fixed LCG keeps the games the same from run to run
+        let mut state = 0x9e37_79b9_7f4a_7c15_u64;
+        let mut next = |bound: usize| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) as usize % bound
+        };
+
+        assert_eq!(Board::default().position_string(), EMPTY);
+
+        for _ in 0..50 {
+            let mut board = Board::default();
+
+            loop {
+                assert_eq!(board.position_string().parse::<Board>().unwrap(), board);
+
+                let moves = board.moves();
+                if moves.is_empty() {
+                    break;
+                }
+                board = board.play_move(moves[next(moves.len())]).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn malformed_positions_are_rejected() {
+        let bad = [
+            // A local board one square short
+            "......../........./........./........./........./........./........./........./......... X -",
+            // Only eight local boards
+            "........./........./........./........./........./........./........./......... X -",
+            // A square that is neither X, O nor empty
+            "....Z..../........./........./........./........./........./........./........./......... X -",
+            // No one to move
+            "........./........./........./........./........./........./........./........./......... Y -",
+            // Forced into a board that does not exist
+            "........./........./........./........./........./........./........./........./......... X 9",
+            "........./........./........./........./........./........./........./........./......... X x",
+            // Forced into a board X has already won
+            "XXX....../........./........./........./........./........./........./........./......... O 0",
+            // Something after the position
+            "........./........./........./........./........./........./........./........./......... X - X",
+        ];
+
+        for position in bad {
+            assert!(position.parse::<Board>().is_err(), "{position}");
+        }
+    }
+}
@@ -0,0 +1,242 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    classic::ClassicBoard,
+    game::{mix, Game, Outcome},
+    general::GeneralBoard,
+    Board, IndividualBoard, Player, Score,
+};
+
+/// A game whose two sides can trade places.
+pub trait Swappable: Game {
+    /// Recolors every mark and hands the move to the other side.
+    fn swap_sides(&self) -> Self;
+}
+
+impl Swappable for Board {
+    fn swap_sides(&self) -> Self {
+        Board {
+            locals_x: self.locals_o,
+            locals_o: self.locals_x,
+            global_x: self.global_o,
+            global_o: self.global_x,
+            global_full: self.global_full,
+            to_play: self.to_play.invert(),
+            global_idx: self.global_idx,
+        }
+    }
+}
+
+impl Swappable for ClassicBoard {
+    fn swap_sides(&self) -> Self {
+        ClassicBoard {
+            board: IndividualBoard(self.board.1, self.board.0),
+            to_play: self.to_play.invert(),
+        }
+    }
+}
+
+impl Swappable for GeneralBoard {
+    fn swap_sides(&self) -> Self {
+        let mut new_self = self.to_owned();
+
+        for cell in new_self.cells.iter_mut().flatten() {
+            *cell = cell.invert();
+        }
+        for state in new_self.globals.iter_mut().flatten() {
+            if let Outcome::Win(player) = state {
+                *player = player.invert();
+            }
+        }
+        new_self.to_play = self.to_play.invert();
+
+        new_self
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum PieMove<M> {
+    Play(M),
+    /// Claim the opening move, after which the opponent moves again.
+    Swap,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum PieStage {
+    /// No move has been played yet.
+    Opening,
+    /// X has played the opening move and O may swap.
+    SwapOffered,
+    /// O played on instead of swapping, or the rule is turned off.
+    Kept,
+    Swapped,
+}
+
+/// Plays `game` with the pie rule: after X's first move O may take that move as their own.
+///
+/// Marks always belong to the same person, so a swap recolors the opening move to O and gives
+/// the move back to X, who now has to answer it.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct PieRule<G> {
+    pub game: G,
+    pub stage: PieStage,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PieResult<M> {
+    pub mv: PieMove<M>,
+    pub eval: Score,
+}
+
+impl<G: Swappable> PieRule<G> {
+    pub fn new(game: G) -> Self {
+        PieRule {
+            game,
+            stage: PieStage::Opening,
+        }
+    }
+
+    /// Wraps `game` without ever offering a swap.
+    pub fn without_swap(game: G) -> Self {
+        PieRule {
+            game,
+            stage: PieStage::Kept,
+        }
+    }
+}
+
+impl<G: Swappable> Game for PieRule<G> {
+    type Move = PieMove<G::Move>;
+
+    fn moves(&self) -> Vec<PieMove<G::Move>> {
+        let mut moves = self
+            .game
+            .moves()
+            .into_iter()
+            .map(PieMove::Play)
+            .collect::<Vec<_>>();
+
+        if self.stage == PieStage::SwapOffered && !moves.is_empty() {
+            moves.push(PieMove::Swap);
+        }
+
+        moves
+    }
+
+    fn play_move(&self, mv: PieMove<G::Move>) -> Option<Self> {
+        match mv {
+            PieMove::Play(mv) => Some(PieRule {
+                game: self.game.play_move(mv)?,
+                stage: match self.stage {
+                    PieStage::Opening => PieStage::SwapOffered,
+                    PieStage::SwapOffered => PieStage::Kept,
+                    stage => stage,
+                },
+            }),
+            PieMove::Swap if self.stage == PieStage::SwapOffered => Some(PieRule {
+                game: self.game.swap_sides(),
+                stage: PieStage::Swapped,
+            }),
+            PieMove::Swap => None,
+        }
+    }
+
+    fn outcome(&self) -> Option<Outcome> {
+        self.game.outcome()
+    }
+
+    fn side_to_move(&self) -> Player {
+        self.game.side_to_move()
+    }
+
+    fn hash_key(&self) -> u64 {
+        mix(self.game.hash_key(), self.stage as u64)
+    }
+
+    fn move_priority(&self, mv: PieMove<G::Move>) -> i32 {
+        match mv {
+            PieMove::Play(mv) => self.game.move_priority(mv),
+            PieMove::Swap => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plays<G: Swappable>(game: &G) -> Vec<PieMove<G::Move>> {
+        game.moves().into_iter().map(PieMove::Play).collect()
+    }
+
+    #[test]
+    fn swap_is_offered_once_after_the_opening_move() {
+        let opening = PieRule::new(Board::default());
+        assert_eq!(opening.moves(), plays(&opening.game));
+        assert!(opening.play_move(PieMove::Swap).is_none());
+
+        let offered = opening.play_move(PieMove::Play((4, 4))).unwrap();
+        assert_eq!(offered.stage, PieStage::SwapOffered);
+        assert_eq!(offered.moves().last(), Some(&PieMove::Swap));
+
+        let swapped = offered.play_move(PieMove::Swap).unwrap();
+        assert_eq!(swapped.stage, PieStage::Swapped);
+        assert_eq!(swapped.moves(), plays(&swapped.game));
+        assert!(swapped.play_move(PieMove::Swap).is_none());
+
+        let kept = offered.play_move(PieMove::Play((4, 0))).unwrap();
+        assert_eq!(kept.stage, PieStage::Kept);
+        assert_eq!(kept.moves(), plays(&kept.game));
+        assert!(kept.play_move(PieMove::Swap).is_none());
+    }
+
+    #[test]
+    fn without_swap_never_offers_one() {
+        let game = PieRule::without_swap(Board::default())
+            .play_move(PieMove::Play((4, 4)))
+            .unwrap();
+
+        assert_eq!(game.stage, PieStage::Kept);
+        assert!(!game.moves().contains(&PieMove::Swap));
+    }
+
+    #[test]
+    fn swap_recolors_the_opening_move() {
+        let swapped = PieRule::new(Board::default())
+            .play_move(PieMove::Play((4, 4)))
+            .unwrap()
+            .play_move(PieMove::Swap)
+            .unwrap();
+
+        let opening = Board::default().play(4, 4).unwrap();
+        assert_eq!(swapped.game.locals_x, 0);
+        assert_eq!(swapped.game.locals_o, opening.locals_x);
+        assert_eq!(swapped.side_to_move(), Player::X);
+
+        let classic = ClassicBoard::default().play(4).unwrap().swap_sides();
+        assert_eq!(classic.board, IndividualBoard(0, 1 << 4));
+        assert_eq!(classic.to_play, Player::X);
+
+        let general = GeneralBoard::new(3, 3).play(4, 4).unwrap();
+        let swapped = general.swap_sides();
+        assert_eq!(swapped.cells[4 * 9 + 4], Some(Player::O));
+        assert_eq!(swapped.to_play, Player::X);
+        assert_eq!(swapped.swap_sides(), general);
+    }
+
+    #[test]
+    fn hash_depends_on_the_stage() {
+        let board = Board::default().play(4, 4).unwrap();
+        let offered = PieRule {
+            game: board.clone(),
+            stage: PieStage::SwapOffered,
+        };
+        let kept = PieRule {
+            game: board,
+            stage: PieStage::Kept,
+        };
+
+        assert_ne!(offered.hash_key(), kept.hash_key());
+        assert_eq!(offered.hash_key(), offered.clone().hash_key());
+    }
+}
@@ -0,0 +1,117 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Player;
+
+/// A search score from X's point of view.
+///
+/// Heuristic evaluations are stored in ten thousandths. Wins sit far above any heuristic value
+/// and count down with every ply it takes to reach them, so a faster win always scores better
+/// than a slower one and a slower loss better than a faster one.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct Score(pub i32);
+
+const WIN: i32 = 1_000_000;
+/// Longest distance to a win a score can hold, far more than the 81 plies a game can last.
+const MAX_PLIES: i32 = 1_000;
+const EVAL_SCALE: f64 = 10_000.0;
+
+impl Score {
+    pub const DRAW: Score = Score(0);
+    /// Below every reachable score, for opening a search window.
+    pub const MIN: Score = Score(-WIN - 1);
+    /// Above every reachable score, for opening a search window.
+    pub const MAX: Score = Score(WIN + 1);
+
+    /// A win for `player` that is `plies` plies away.
+    pub fn win_in(player: Player, plies: u32) -> Self {
+        let value = WIN - (plies as i32).min(MAX_PLIES);
+
+        match player {
+            Player::X => Score(value),
+            Player::O => Score(-value),
+        }
+    }
+
+    /// Converts a heuristic evaluation, keeping it clear of the win scores.
+    pub fn from_eval(eval: f64) -> Self {
+        let limit = (WIN - MAX_PLIES - 1) as f64;
+        Score((eval * EVAL_SCALE).round().clamp(-limit, limit) as i32)
+    }
+
+    /// The heuristic evaluation this score stands for, or ±[`f64::INFINITY`] for a win.
+    pub fn to_eval(self) -> f64 {
+        match self.win() {
+            Some((Player::X, _)) => f64::INFINITY,
+            Some((Player::O, _)) => f64::NEG_INFINITY,
+            None => self.0 as f64 / EVAL_SCALE,
+        }
+    }
+
+    /// The winner and the number of plies until they win, if this score is a forced win.
+    pub fn win(self) -> Option<(Player, u32)> {
+        if self.0 >= WIN - MAX_PLIES {
+            Some((Player::X, (WIN - self.0).max(0) as u32))
+        } else if self.0 <= -(WIN - MAX_PLIES) {
+            Some((Player::O, (WIN + self.0).max(0) as u32))
+        } else {
+            None
+        }
+    }
+}
+
+impl Display for Score {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.win() {
+            Some((player, plies)) => {
+                write!(f, "{} wins in {}", Player::to_char(Some(player)), plies)
+            }
+            None => write!(f, "{:+.4}", self.to_eval()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn faster_wins_score_better() {
+        assert!(Score::win_in(Player::X, 1) > Score::win_in(Player::X, 5));
+        assert!(Score::win_in(Player::O, 1) < Score::win_in(Player::O, 5));
+
+        // Even the slowest win beats every heuristic value
+        assert!(Score::win_in(Player::X, 81) > Score::from_eval(f64::MAX));
+        assert!(Score::win_in(Player::O, 81) < Score::from_eval(f64::MIN));
+        assert!(Score::MIN < Score::win_in(Player::O, 0));
+        assert!(Score::MAX > Score::win_in(Player::X, 0));
+    }
+
+    #[test]
+    fn wins_keep_their_distance() {
+        for plies in [0, 1, 9, 81] {
+            assert_eq!(
+                Score::win_in(Player::X, plies).win(),
+                Some((Player::X, plies))
+            );
+            assert_eq!(
+                Score::win_in(Player::O, plies).win(),
+                Some((Player::O, plies))
+            );
+        }
+
+        assert_eq!(Score::from_eval(0.75).win(), None);
+        assert_eq!(Score::from_eval(0.75).to_eval(), 0.75);
+    }
+
+    #[test]
+    fn display_names_the_winner() {
+        assert_eq!(Score::win_in(Player::X, 3).to_string(), "X wins in 3");
+        assert_eq!(Score::win_in(Player::O, 12).to_string(), "O wins in 12");
+        assert_eq!(Score::from_eval(-0.25).to_string(), "-0.2500");
+    }
+}
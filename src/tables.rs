@@ -0,0 +1,186 @@
+//! Lazily built tables of the random playout values behind [`crate::evalute`] and
+//! [`crate::evaluate_whole`].
+//!
+//! Every local board and every arrangement of decided local boards is valued once, reusing the
+//! values of the positions that follow it, instead of walking every completion on each call.
+
+use std::sync::OnceLock;
+
+use crate::{Board, IndividualBoard, Player};
+
+/// Number of local boards, one digit per square: empty, X or O.
+pub const LOCAL_STATES: usize = 19683;
+/// Number of global boards, one digit per local board: undecided, X, O or tied.
+pub const GLOBAL_STATES: usize = 262144;
+
+struct Tables {
+    local: Vec<[f64; 2]>,
+    global: Vec<[f64; 2]>,
+}
+
+static TABLES: OnceLock<Tables> = OnceLock::new();
+
+fn tables() -> &'static Tables {
+    TABLES.get_or_init(|| {
+        let mut local = vec![[f64::NAN; 2]; LOCAL_STATES];
+        let mut global = vec![[f64::NAN; 2]; GLOBAL_STATES];
+
+        for idx in 0..LOCAL_STATES {
+            let board = local_board(idx);
+            fill_local(&mut local, board, Player::X);
+            fill_local(&mut local, board, Player::O);
+        }
+
+        for idx in 0..GLOBAL_STATES {
+            let (x, o, full) = global_board(idx);
+            fill_global(&mut global, x, o, full, Player::X);
+            fill_global(&mut global, x, o, full, Player::O);
+        }
+
+        Tables { local, global }
+    })
+}
+
+/// Dense index of a local board, `None` if a square is claimed by both players.
+pub fn local_index(board: IndividualBoard) -> Option<usize> {
+    if board.0 & board.1 != 0 {
+        return None;
+    }
+
+    Some((0..9).rev().fold(0, |acc, idx| {
+        acc * 3 + ((board.0 >> idx) & 1) as usize + 2 * ((board.1 >> idx) & 1) as usize
+    }))
+}
+
+/// The local board at a dense index, the inverse of [`local_index`].
+pub fn local_board(mut idx: usize) -> IndividualBoard {
+    let mut board = IndividualBoard(0, 0);
+
+    for square in 0..9 {
+        match idx % 3 {
+            1 => board.0 |= 1 << square,
+            2 => board.1 |= 1 << square,
+            _ => {}
+        }
+        idx /= 3;
+    }
+
+    board
+}
+
+/// Dense index of the decided local boards of a board, `None` if they overlap.
+pub fn global_index(global_x: u16, global_o: u16, global_full: u16) -> Option<usize> {
+    if global_x & global_o != 0 || (global_x | global_o) & global_full != 0 {
+        return None;
+    }
+
+    Some((0..9).rev().fold(0, |acc, idx| {
+        acc * 4
+            + ((global_x >> idx) & 1) as usize
+            + 2 * ((global_o >> idx) & 1) as usize
+            + 3 * ((global_full >> idx) & 1) as usize
+    }))
+}
+
+/// The `(global_x, global_o, global_full)` masks at a dense index, the inverse of [`global_index`].
+pub fn global_board(mut idx: usize) -> (u16, u16, u16) {
+    let (mut x, mut o, mut full) = (0, 0, 0);
+
+    for board in 0..9 {
+        match idx % 4 {
+            1 => x |= 1 << board,
+            2 => o |= 1 << board,
+            3 => full |= 1 << board,
+            _ => {}
+        }
+        idx /= 4;
+    }
+
+    (x, o, full)
+}
+
+fn fill_local(table: &mut [[f64; 2]], board: IndividualBoard, player: Player) -> f64 {
+    let idx = local_index(board).expect("squares are never claimed twice");
+
+    if !table[idx][player as usize].is_nan() {
+        return table[idx][player as usize];
+    }
+
+    let value = if board.is_tie() {
+        0.0
+    } else if let Some(winner) = board.has_won() {
+        match winner {
+            Player::X => 1.0,
+            Player::O => -1.0,
+        }
+    } else {
+        (0..9)
+            .filter(|idx| ((board.0 | board.1) >> idx) & 1 == 0)
+            .map(|idx| {
+                let mut new_board = board;
+                if player == Player::X {
+                    new_board.0 |= 1 << idx;
+                } else {
+                    new_board.1 |= 1 << idx;
+                }
+                fill_local(table, new_board, player.invert())
+            })
+            .sum::<f64>()
+            / 9.0
+    };
+
+    table[idx][player as usize] = value;
+    value
+}
+
+fn fill_global(table: &mut [[f64; 2]], x: u16, o: u16, full: u16, player: Player) -> f64 {
+    let idx = global_index(x, o, full).expect("local boards are never decided twice");
+
+    if !table[idx][player as usize].is_nan() {
+        return table[idx][player as usize];
+    }
+
+    let board = Board {
+        global_x: x,
+        global_o: o,
+        global_full: full,
+        ..Board::default()
+    };
+
+    let value = if board.is_tie() {
+        0.0
+    } else if let Some(winner) = board.has_won() {
+        match winner {
+            Player::X => 1.0,
+            Player::O => -1.0,
+        }
+    } else {
+        (0..9)
+            .filter(|idx| ((x | o | full) >> idx) & 1 == 0)
+            .map(|idx| {
+                if player == Player::X {
+                    fill_global(table, x | 1 << idx, o, full, player.invert())
+                } else {
+                    fill_global(table, x, o | 1 << idx, full, player.invert())
+                }
+            })
+            .sum::<f64>()
+            / 9.0
+    };
+
+    table[idx][player as usize] = value;
+    value
+}
+
+/// The value of a local board when `player` moves next, see [`crate::evalute`].
+pub fn local_value(board: IndividualBoard, player: Player) -> f64 {
+    tables().local[local_index(board).expect("squares are never claimed twice")][player as usize]
+}
+
+/// The value of the decided local boards when `player` moves next, see [`crate::evaluate_whole`].
+pub fn global_value(board: &Board, player: Player) -> f64 {
+    let idx = global_index(board.global_x, board.global_o, board.global_full)
+        .expect("local boards are never decided twice");
+
+    tables().global[idx][player as usize]
+}